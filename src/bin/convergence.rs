@@ -0,0 +1,169 @@
+//! Grid convergence study.
+//!
+//! Runs the same scenarios at several cell sizes and time steps, reports the error norms
+//! against the finest run and estimates the order of convergence.
+//! Run with `cargo run --release --bin convergence`.
+
+extern crate quantum_wave;
+
+use quantum_wave::{Complex, Coord, Universe};
+
+/// Side of the square domain, in physical length units.
+const LENGTH: f32 = 16.0;
+/// Simulated physical time.
+const DURATION: f32 = 1.0;
+
+/// Scenario set up in physical units on a universe with cells of size h.
+/// The solver works in cell units, where lengths scale by 1/h, momenta by h,
+/// and potentials and times by h² and 1/h². Packets normalized in cell units
+/// carry a factor h compared to the physical wavefunction.
+struct Scenario {
+    name: &'static str,
+    setup: fn(&mut Universe, f32),
+}
+
+fn moving_gaussian(u: &mut Universe, h: f32) {
+    let amplitude = Complex::new(1.0, 0.0);
+    u.add_wavepacket(7.5 / h, 8.0 / h, 1.25 / h, 0.5 * h, 0.25 * h, amplitude);
+}
+
+fn harmonic_coherent_state(u: &mut Universe, h: f32) {
+    let (omega, center) = (1.0, (LENGTH / 2.0 / h) as i32);
+    u.add_harmonic_potential(
+        Coord::new(center, center),
+        0.0,
+        omega * h * h,
+        omega * h * h,
+    );
+    u.add_coherent_state(
+        6.5 / h,
+        8.0 / h,
+        omega * h * h,
+        0.0,
+        0.0,
+        Complex::new(1.0, 0.0),
+    );
+}
+
+const SCENARIOS: [Scenario; 2] = [
+    Scenario {
+        name: "moving gaussian",
+        setup: moving_gaussian,
+    },
+    Scenario {
+        name: "harmonic coherent state",
+        setup: harmonic_coherent_state,
+    },
+];
+
+/// Run a scenario with cell size h and physical time step dt.
+/// Returns the physical wavefunction ψ = ψ_cell / h with its width in cells.
+/// The border cells sit at 0 and LENGTH whatever the cell size, so that nodes line up.
+fn run(scenario: &Scenario, h: f32, dt: f32) -> (usize, Vec<Complex>) {
+    let cells = (LENGTH / h).round() as usize + 1;
+    let mut u = Universe::new(cells, cells);
+    u.setup();
+    u.set_dt(dt / (h * h));
+    (scenario.setup)(&mut u, h);
+    let steps = (DURATION / dt).round() as usize;
+    for _i in 0..steps {
+        u.step();
+    }
+    let state = u
+        .quantum_state()
+        .chunks(2)
+        .map(|pair| Complex::new(pair[0] / h, pair[1] / h))
+        .collect();
+    (cells, state)
+}
+
+/// Discrete L2 error of a coarse run against the reference, on the coarse nodes.
+fn l2_error(coarse: &(usize, Vec<Complex>), reference: &(usize, Vec<Complex>), h: f32) -> f32 {
+    let ratio = (reference.0 - 1) / (coarse.0 - 1);
+    let mut sum = 0.0;
+    for (i, value) in coarse.1.iter().enumerate() {
+        let (x, y) = (i % coarse.0, i / coarse.0);
+        let exact = reference.1[x * ratio + y * ratio * reference.0];
+        sum += value.sub(&exact).norm() * h * h;
+    }
+    sum.sqrt()
+}
+
+/// Study of a sequence of runs refined by a factor two, the last one being the finest.
+/// Errors are measured against the finest run on the coarse nodes; orders are estimated from
+/// the differences between successive runs, which does not need an exact solution.
+struct Study {
+    errors: Vec<f32>,
+    orders: Vec<f32>,
+}
+
+impl Study {
+    fn new(runs: &[(usize, Vec<Complex>)], cell_sizes: &[f32]) -> Study {
+        let finest = runs.last().unwrap();
+        let errors = runs[..runs.len() - 1]
+            .iter()
+            .zip(cell_sizes.iter())
+            .map(|(run, &h)| l2_error(run, finest, h))
+            .collect();
+        let differences: Vec<f32> = runs
+            .windows(2)
+            .zip(cell_sizes.iter())
+            .map(|(pair, &h)| l2_error(&pair[0], &pair[1], h))
+            .collect();
+        let orders = differences
+            .windows(2)
+            .map(|d| (d[0] / d[1]).log2())
+            .collect();
+        Study { errors, orders }
+    }
+
+    fn report(&self, label: &str, parameters: &[f32]) {
+        println!("  {:>10} {:>12}", label, "L2 error");
+        for (parameter, error) in parameters.iter().zip(self.errors.iter()) {
+            println!("  {:>10.5} {:>12.3e}", parameter, error);
+        }
+        let orders: Vec<String> = self.orders.iter().map(|p| format!("{:.2}", p)).collect();
+        println!("  observed order: {}", orders.join(", "));
+    }
+}
+
+/// Runs halving the cell size, with dt small enough for the time error to vanish.
+fn spatial_study(scenario: &Scenario, sizes: &[f32]) -> Study {
+    let finest = *sizes.last().unwrap();
+    let dt = 0.1 * finest * finest;
+    let runs: Vec<_> = sizes.iter().map(|&h| run(scenario, h, dt)).collect();
+    Study::new(&runs, sizes)
+}
+
+/// Runs halving the time step at a fixed cell size.
+fn temporal_study(scenario: &Scenario, h: f32, steps: &[f32]) -> Study {
+    let runs: Vec<_> = steps.iter().map(|&dt| run(scenario, h, dt)).collect();
+    let sizes = vec![h; steps.len()];
+    Study::new(&runs, &sizes)
+}
+
+fn main() {
+    let sizes = [0.5, 0.25, 0.125, 0.0625];
+    let h = 0.25;
+    let steps = [0.01, 0.005, 0.0025, 0.00125];
+    for scenario in SCENARIOS.iter() {
+        println!("{}", scenario.name);
+        spatial_study(scenario, &sizes).report("cell size", &sizes);
+        temporal_study(scenario, h, &steps).report("dt", &steps);
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn stencil_is_second_order_in_space() {
+    let study = spatial_study(&SCENARIOS[0], &[0.5, 0.25, 0.125]);
+    assert!(study.errors[0] > study.errors[1]);
+    assert!((study.orders[0] - 2.0).abs() < 0.3, "{:?}", study.orders);
+}
+
+#[test]
+fn leapfrog_is_second_order_in_time() {
+    let study = temporal_study(&SCENARIOS[1], 0.5, &[0.04, 0.02, 0.01]);
+    assert!(study.errors[0] > study.errors[1]);
+    assert!((study.orders[0] - 2.0).abs() < 0.3, "{:?}", study.orders);
+}
@@ -0,0 +1,274 @@
+//! Full-screen terminal viewer.
+//!
+//! Animates the simulation in place, two cells per character with upper half blocks.
+//! Colors follow the terminal: truecolor, 256 or 16 colors, or none when NO_COLOR is set;
+//! `--colors=truecolor|256|16|none` overrides the guess.
+//! Keys: space or p pauses, n steps once while paused, arrows or w a s d tilt the potential,
+//! 0 levels it, c cycles the colormaps, r restarts and q quits.
+//! Run with `cargo run --release --bin viewer`. Unix terminals only, raw mode is set with stty.
+
+extern crate quantum_wave;
+
+use quantum_wave::{Colormap, Complex, Coord, Palette, Universe};
+use std::env;
+use std::io::{self, Read, Write};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+/// Steps computed between two frames while running.
+const STEPS_PER_FRAME: usize = 4;
+const FRAME_TIME: Duration = Duration::from_millis(33);
+/// Slope change for each tilt key press, as a fraction of the maximum tilt.
+const TILT_STEP: f32 = 0.25;
+
+const COLORMAPS: [Colormap; 8] = [
+    Colormap::PhaseDark,
+    Colormap::PhaseLight,
+    Colormap::PhaseColorblind,
+    Colormap::Viridis,
+    Colormap::Magma,
+    Colormap::Cividis,
+    Colormap::RealPart,
+    Colormap::ImaginaryPart,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Key {
+    Quit,
+    Pause,
+    Step,
+    Tilt(f32, f32),
+    Level,
+    Colormap,
+    Restart,
+}
+
+/// Keys found in raw terminal input, arrows being sent as ESC [ A to D.
+fn parse_keys(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == 0x1b && i + 2 < bytes.len() && bytes[i + 1] == b'[' {
+            match bytes[i + 2] {
+                b'A' => keys.push(Key::Tilt(0.0, -TILT_STEP)),
+                b'B' => keys.push(Key::Tilt(0.0, TILT_STEP)),
+                b'C' => keys.push(Key::Tilt(TILT_STEP, 0.0)),
+                b'D' => keys.push(Key::Tilt(-TILT_STEP, 0.0)),
+                _ => {}
+            }
+            i += 3;
+            continue;
+        }
+        match bytes[i] {
+            b'q' | 3 => keys.push(Key::Quit),
+            b' ' | b'p' => keys.push(Key::Pause),
+            b'n' => keys.push(Key::Step),
+            b'w' => keys.push(Key::Tilt(0.0, -TILT_STEP)),
+            b's' => keys.push(Key::Tilt(0.0, TILT_STEP)),
+            b'd' => keys.push(Key::Tilt(TILT_STEP, 0.0)),
+            b'a' => keys.push(Key::Tilt(-TILT_STEP, 0.0)),
+            b'0' => keys.push(Key::Level),
+            b'c' => keys.push(Key::Colormap),
+            b'r' => keys.push(Key::Restart),
+            _ => {}
+        }
+        i += 1;
+    }
+    keys
+}
+
+/// Palette forced by a `--colors=` argument, if any.
+fn palette_argument<I: Iterator<Item = String>>(mut args: I) -> Option<Palette> {
+    args.find_map(|arg| match arg.as_str() {
+        "--colors=truecolor" => Some(Palette::TrueColor),
+        "--colors=256" => Some(Palette::Ansi256),
+        "--colors=16" => Some(Palette::Ansi16),
+        "--colors=none" => Some(Palette::Monochrome),
+        _ => None,
+    })
+}
+
+fn stty(args: &[&str]) -> Option<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()
+        .ok()?;
+    if output.status.success() {
+        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        None
+    }
+}
+
+/// Terminal in raw mode on the alternate screen, restored when dropped.
+struct Screen {
+    saved: String,
+}
+
+impl Screen {
+    fn open() -> Option<Screen> {
+        let saved = stty(&["-g"])?;
+        stty(&["raw", "-echo"])?;
+        print!("\x1b[?1049h\x1b[?25l\x1b[2J");
+        Some(Screen { saved })
+    }
+
+    /// Rows and columns of the terminal.
+    fn size() -> (usize, usize) {
+        stty(&["size"])
+            .and_then(|size| {
+                let mut parts = size.split_whitespace().map(|n| n.parse().ok());
+                Some((parts.next()??, parts.next()??))
+            })
+            .unwrap_or((24, 80))
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        print!("\x1b[0m\x1b[?25h\x1b[?1049l");
+        io::stdout().flush().ok();
+        stty(&[&self.saved]);
+    }
+}
+
+/// Bytes typed on stdin, read on a separate thread so the animation never blocks.
+fn spawn_input() -> Receiver<Vec<u8>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buffer = [0u8; 64];
+        while let Ok(n) = io::stdin().read(&mut buffer) {
+            if n == 0 || sender.send(buffer[..n].to_vec()).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+/// A packet heading to a barrier, filling the terminal with one status line left.
+fn scene(rows: usize, columns: usize) -> Universe {
+    let (width, height) = (columns.max(8), (2 * rows.saturating_sub(1)).max(8));
+    let mut u = Universe::new(width, height);
+    u.setup();
+    u.add_potential_barrier(
+        Coord::new(2 * width as i32 / 3, height as i32 / 2),
+        0.0,
+        2.0,
+        0.4,
+    );
+    let sigma = height as f32 / 8.0;
+    let amplitude = Complex::new(1.0, 0.0);
+    u.add_wavepacket(
+        width as f32 / 4.0,
+        height as f32 / 2.0,
+        sigma,
+        0.6,
+        0.0,
+        amplitude,
+    );
+    u.set_auto_exposure(0.99, 0.2);
+    u
+}
+
+fn main() {
+    let palette = palette_argument(env::args().skip(1)).unwrap_or_else(Palette::from_env);
+    let (rows, columns) = Screen::size();
+    let mut u = scene(rows, columns);
+    let screen = match Screen::open() {
+        Some(screen) => screen,
+        None => {
+            eprintln!("the viewer needs a terminal and the stty command");
+            return;
+        }
+    };
+    let input = spawn_input();
+    let (mut paused, mut tilt, mut colormap) = (false, (0.0f32, 0.0f32), 0);
+    let mut steps = 0;
+    let stdout = io::stdout();
+    loop {
+        let mut advance = if paused { 0 } else { STEPS_PER_FRAME };
+        let bytes: Vec<u8> = input.try_iter().flatten().collect();
+        for key in parse_keys(&bytes) {
+            match key {
+                Key::Quit => {
+                    drop(screen);
+                    return;
+                }
+                Key::Pause => paused = !paused,
+                Key::Step => advance += 1,
+                Key::Tilt(dx, dy) => {
+                    tilt.0 = (tilt.0 + dx).clamp(-1.0, 1.0);
+                    tilt.1 = (tilt.1 + dy).clamp(-1.0, 1.0);
+                }
+                Key::Level => tilt = (0.0, 0.0),
+                Key::Colormap => {
+                    colormap = (colormap + 1) % COLORMAPS.len();
+                    u.set_colormap(COLORMAPS[colormap]);
+                }
+                Key::Restart => {
+                    u = scene(rows, columns);
+                    u.set_colormap(COLORMAPS[colormap]);
+                    steps = 0;
+                }
+            }
+        }
+        u.set_tilt(tilt.0, tilt.1);
+        for _ in 0..advance {
+            u.step();
+        }
+        steps += advance;
+        u.render();
+
+        let mut out = stdout.lock();
+        let frame = u.terminal_frame(palette).replace('\n', "\r\n");
+        write!(out, "\x1b[H{}", frame).ok();
+        write!(
+            out,
+            "\x1b[K{} step {}  tilt {:+.2} {:+.2}  {:?}  norm {:.3}",
+            if paused { "paused" } else { "running" },
+            steps,
+            tilt.0,
+            tilt.1,
+            COLORMAPS[colormap],
+            u.norm()
+        )
+        .ok();
+        out.flush().ok();
+        drop(out);
+        thread::sleep(FRAME_TIME);
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn parse_raw_keys() {
+    let keys = parse_keys(b"p\x1b[Cnq\x1b[Ax");
+    assert_eq!(
+        keys,
+        vec![
+            Key::Pause,
+            Key::Tilt(TILT_STEP, 0.0),
+            Key::Step,
+            Key::Quit,
+            Key::Tilt(0.0, -TILT_STEP)
+        ]
+    );
+}
+
+#[test]
+fn colors_argument_overrides_the_environment() {
+    let args = vec!["--colors=256".to_string()];
+    assert_eq!(palette_argument(args.into_iter()), Some(Palette::Ansi256));
+    assert_eq!(palette_argument(Vec::new().into_iter()), None);
+}
+
+#[test]
+fn scene_fills_the_terminal() {
+    let mut u = scene(25, 60);
+    u.step();
+    assert_eq!(u.terminal_frame(Palette::Monochrome).lines().count(), 24);
+}
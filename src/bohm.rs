@@ -0,0 +1,137 @@
+//! Bohmian trajectories: tracer particles advected along the velocity field j/ρ.
+
+use complex::Complex;
+use grid::Grid;
+use rand::Rng;
+
+/// Tracer particles, positions in cell units with cell centers on integer coordinates.
+#[derive(Clone, Debug, Default)]
+pub struct Tracers {
+    pub positions: Vec<(f32, f32)>,
+    pub stopped: Vec<bool>,
+}
+
+/// Bilinear interpolation of a per-cell value, zero outside the grid.
+fn interpolate<T, F: Fn(&T) -> f32>(grid: &Grid<T>, x: f32, y: f32, f: F) -> f32 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let at = |i: f32, j: f32| {
+        if i < 0.0 || j < 0.0 || i >= grid.width as f32 || j >= grid.height as f32 {
+            0.0
+        } else {
+            f(&grid.data[i as usize + j as usize * grid.width])
+        }
+    };
+    (at(x0, y0) * (1.0 - fx) + at(x0 + 1.0, y0) * fx) * (1.0 - fy)
+        + (at(x0, y0 + 1.0) * (1.0 - fx) + at(x0 + 1.0, y0 + 1.0) * fx) * fy
+}
+
+/// Bohmian velocity j/ρ at a point, interpolating current and density separately.
+pub fn velocity(quantum: &Grid<Complex>, current: &Grid<(f32, f32)>, x: f32, y: f32) -> (f32, f32) {
+    let density = interpolate(quantum, x, y, |c| c.norm());
+    if density <= 0.0 {
+        return (0.0, 0.0);
+    }
+    (
+        interpolate(current, x, y, |j| j.0) / density,
+        interpolate(current, x, y, |j| j.1) / density,
+    )
+}
+
+impl Tracers {
+    /// Draw n positions distributed as |ψ|², jittered uniformly within their cell.
+    pub fn sample<R: Rng>(quantum: &Grid<Complex>, n: usize, rng: &mut R) -> Tracers {
+        let mut cumulative = Vec::with_capacity(quantum.data.len());
+        let mut total = 0.0;
+        for c in quantum.data.iter() {
+            total += c.norm();
+            cumulative.push(total);
+        }
+        let mut tracers = Tracers::default();
+        if total <= 0.0 {
+            return tracers;
+        }
+        for _i in 0..n {
+            let target = rng.gen::<f32>() * total;
+            let index = cumulative
+                .partition_point(|&c| c <= target)
+                .min(cumulative.len() - 1);
+            let x = (index % quantum.width) as f32 + rng.gen::<f32>() - 0.5;
+            let y = (index / quantum.width) as f32 + rng.gen::<f32>() - 0.5;
+            tracers.positions.push((x, y));
+            tracers.stopped.push(false);
+        }
+        tracers
+    }
+
+    /// Move every running tracer over dt with a midpoint step.
+    /// Tracers entering a blocked cell, or leaving the grid, stop where they are.
+    pub fn advect<F: Fn(i32, i32) -> bool>(
+        &mut self,
+        quantum: &Grid<Complex>,
+        current: &Grid<(f32, f32)>,
+        dt: f32,
+        blocked: F,
+    ) {
+        for (position, stopped) in self.positions.iter_mut().zip(self.stopped.iter_mut()) {
+            if *stopped {
+                continue;
+            }
+            let (x, y) = *position;
+            let (vx, vy) = velocity(quantum, current, x, y);
+            let (mx, my) = (x + 0.5 * dt * vx, y + 0.5 * dt * vy);
+            let (vx, vy) = velocity(quantum, current, mx, my);
+            let (nx, ny) = (x + dt * vx, y + dt * vy);
+            let (cx, cy) = (nx.round() as i32, ny.round() as i32);
+            let outside =
+                cx < 0 || cy < 0 || cx >= quantum.width as i32 || cy >= quantum.height as i32;
+            if outside || blocked(cx, cy) {
+                *stopped = true;
+            } else {
+                *position = (nx, ny);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+use rand::rngs::StdRng;
+#[cfg(test)]
+use rand::SeedableRng;
+
+#[cfg(test)]
+#[test]
+fn sample_tracers_from_density() {
+    let mut quantum = Grid::<Complex>::new(4, 4);
+    quantum.data[5] = Complex::new(1.0, 0.0);
+    quantum.data[10] = Complex::new(0.0, 1.0);
+    let mut rng = StdRng::seed_from_u64(7);
+    let tracers = Tracers::sample(&quantum, 100, &mut rng);
+    assert_eq!(tracers.positions.len(), 100);
+    for &(x, y) in tracers.positions.iter() {
+        let cell = (x.round(), y.round());
+        assert!(cell == (1.0, 1.0) || cell == (2.0, 2.0));
+    }
+}
+
+#[test]
+fn advect_tracers_along_a_plane_wave() {
+    let k = 0.2;
+    let mut quantum = Grid::<Complex>::new(20, 5);
+    for (i, cell) in quantum.data.iter_mut().enumerate() {
+        *cell = Complex::from_polar(1.0, k * (i % 20) as f32);
+    }
+    let current = quantum.current(&Grid::<bool>::new(20, 5));
+    let mut tracers = Tracers {
+        positions: vec![(5.0, 2.0), (17.0, 2.0)],
+        stopped: vec![false, false],
+    };
+    for _i in 0..10 {
+        tracers.advect(&quantum, &current, 0.5, |x, _| x >= 18);
+    }
+    // lattice velocity of the plane wave is sin k
+    assert!((tracers.positions[0].0 - 5.0 - 5.0 * k.sin()).abs() < 1e-4);
+    assert!((tracers.positions[0].1 - 2.0).abs() < 1e-6);
+    assert!(tracers.stopped[1]);
+    assert!(tracers.positions[1].0 < 17.5);
+}
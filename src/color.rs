@@ -10,12 +10,16 @@ pub struct Color {
     pub b: u8,
 }
 
-/// Color implementation.
+/// Constructors, exported to JavaScript.
+#[wasm_bindgen]
 impl Color {
     pub fn new(r: u8, g: u8, b: u8) -> Color {
         Color { r, g, b }
     }
+}
 
+/// Color implementation.
+impl Color {
     pub fn white() -> Color {
         Color {
             r: 255,
@@ -24,6 +28,11 @@ impl Color {
         }
     }
 
+    /// Solid color used to draw hard walls.
+    pub fn wall() -> Color {
+        Color::new(128, 128, 128)
+    }
+
     pub fn random() -> Color {
         Color::new(
             rand::random::<u8>(),
@@ -39,4 +48,15 @@ impl Color {
     pub fn complementary(&self) -> Color {
         Color::new(255 - self.r, 255 - self.g, 255 - self.b)
     }
+
+    /// Mix with another color, alpha being the weight of the other color.
+    pub fn blend(&self, other: &Color, alpha: f32) -> Color {
+        let alpha = alpha.clamp(0.0, 1.0);
+        let mix = |a: u8, b: u8| (a as f32 * (1.0 - alpha) + b as f32 * alpha).round() as u8;
+        Color::new(
+            mix(self.r, other.r),
+            mix(self.g, other.g),
+            mix(self.b, other.b),
+        )
+    }
 }
@@ -0,0 +1,199 @@
+//! Mapping of field values to colors: phase wheels, density maps and diverging maps.
+
+use color::Color;
+use colorsys::*;
+use complex::Complex;
+use std::f32::consts::PI;
+use wasm_bindgen::prelude::*;
+
+/// Samples of the matplotlib perceptual maps, from low to high values.
+const VIRIDIS: [(u8, u8, u8); 9] = [
+    (68, 1, 84),
+    (71, 44, 122),
+    (59, 81, 139),
+    (44, 113, 142),
+    (33, 144, 141),
+    (39, 173, 129),
+    (92, 200, 99),
+    (170, 220, 50),
+    (253, 231, 37),
+];
+const MAGMA: [(u8, u8, u8); 9] = [
+    (0, 0, 4),
+    (28, 16, 68),
+    (79, 18, 123),
+    (129, 37, 129),
+    (181, 54, 122),
+    (229, 80, 100),
+    (251, 135, 97),
+    (254, 194, 135),
+    (252, 253, 191),
+];
+const CIVIDIS: [(u8, u8, u8); 9] = [
+    (0, 34, 78),
+    (18, 53, 112),
+    (59, 73, 108),
+    (87, 93, 109),
+    (112, 113, 115),
+    (138, 135, 121),
+    (166, 157, 117),
+    (196, 181, 108),
+    (254, 232, 56),
+];
+
+/// Okabe-Ito colors around the phase wheel, distinguishable with the common color vision
+/// deficiencies. The first color is repeated to close the cycle.
+const OKABE_ITO: [(u8, u8, u8); 7] = [
+    (0, 114, 178),
+    (86, 180, 233),
+    (240, 228, 66),
+    (230, 159, 0),
+    (213, 94, 0),
+    (204, 121, 167),
+    (0, 114, 178),
+];
+
+/// Mapping from field values to colors.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Colormap {
+    /// Phase as hue, amplitude as brightness rising from black.
+    #[default]
+    PhaseDark,
+    /// Phase as hue, amplitude as saturation rising from white.
+    PhaseLight,
+    /// Phase on a color blind safe wheel, amplitude as brightness rising from black.
+    PhaseColorblind,
+    /// Probability density only.
+    Viridis,
+    Magma,
+    Cividis,
+    /// Real part, negative values in blue and positive values in red.
+    RealPart,
+    /// Imaginary part, negative values in blue and positive values in red.
+    ImaginaryPart,
+}
+
+impl Colormap {
+    /// Color of a scaled complex amplitude, full brightness being reached at a radius of one.
+    /// Density maps show the square of the radius.
+    pub fn complex(self, value: Complex) -> Color {
+        match self {
+            Colormap::RealPart => diverging(value.re),
+            Colormap::ImaginaryPart => diverging(value.im),
+            Colormap::Viridis | Colormap::Magma | Colormap::Cividis => self.level(value.norm()),
+            _ => self.phase(value.arg(), value.radius()),
+        }
+    }
+
+    /// Color of a real value, in [0, 1] for the density maps and in [-1, 1] for the others.
+    /// Phase maps show negative values with the phase π.
+    pub fn scalar(self, value: f32) -> Color {
+        match self {
+            Colormap::RealPart | Colormap::ImaginaryPart => diverging(value),
+            Colormap::Viridis | Colormap::Magma | Colormap::Cividis => self.level(value),
+            _ => self.phase(if value < 0.0 { PI } else { 0.0 }, value.abs()),
+        }
+    }
+
+    /// True for the maps showing only the probability density.
+    pub fn is_density(self) -> bool {
+        matches!(
+            self,
+            Colormap::Viridis | Colormap::Magma | Colormap::Cividis
+        )
+    }
+
+    /// Color of a phase in radians with a brightness level in [0, 1].
+    fn phase(self, phase: f32, level: f32) -> Color {
+        let level = level.clamp(0.0, 1.0);
+        match self {
+            Colormap::PhaseColorblind => {
+                let t = (phase / (2.0 * PI)).rem_euclid(1.0);
+                Color::new(0, 0, 0).blend(&interpolate(&OKABE_ITO, t), level)
+            }
+            Colormap::PhaseLight => {
+                let h = ((phase * 180. / PI) + 540.) % 360.;
+                hsl(h, 100. * level.ceil(), 100. - 50. * level)
+            }
+            _ => {
+                // inverted hsl, so that a null amplitude is black and the hue turned by 180°
+                let h = ((phase * 180. / PI) + 360.) % 360.;
+                hsl(h, 100. * level.ceil(), 100. - 100. * level).complementary()
+            }
+        }
+    }
+
+    /// Color of a level in [0, 1] on a density map.
+    fn level(self, level: f32) -> Color {
+        let anchors: &[(u8, u8, u8)] = match self {
+            Colormap::Magma => &MAGMA,
+            Colormap::Cividis => &CIVIDIS,
+            _ => &VIRIDIS,
+        };
+        interpolate(anchors, level)
+    }
+}
+
+/// Diverging blue, white, red color for a value in [-1, 1].
+pub fn diverging(value: f32) -> Color {
+    let value = value.clamp(-1.0, 1.0);
+    if value < 0.0 {
+        Color::white().blend(&Color::new(33, 102, 172), -value)
+    } else {
+        Color::white().blend(&Color::new(178, 24, 43), value)
+    }
+}
+
+/// Color from hue in degrees, saturation and lightness in percent.
+fn hsl(h: f32, s: f32, l: f32) -> Color {
+    let rgb: Rgb = Rgb::from(&Hsl::from((h as f64, s as f64, l as f64)));
+    Color::new(rgb.red() as u8, rgb.green() as u8, rgb.blue() as u8)
+}
+
+/// Linear interpolation between evenly spaced anchors for t in [0, 1].
+fn interpolate(anchors: &[(u8, u8, u8)], t: f32) -> Color {
+    let position = t.clamp(0.0, 1.0) * (anchors.len() - 1) as f32;
+    let i = (position.floor() as usize).min(anchors.len() - 2);
+    let (a, b) = (anchors[i], anchors[i + 1]);
+    Color::new(a.0, a.1, a.2).blend(&Color::new(b.0, b.1, b.2), position - i as f32)
+}
+
+#[cfg(test)]
+#[test]
+fn colormaps_of_a_null_amplitude() {
+    let zero = Complex::zero();
+    assert_eq!(Colormap::PhaseDark.complex(zero), Color::new(0, 0, 0));
+    assert_eq!(Colormap::PhaseLight.complex(zero), Color::white());
+    assert_eq!(Colormap::PhaseColorblind.complex(zero), Color::new(0, 0, 0));
+    assert_eq!(Colormap::Viridis.complex(zero), Color::new(68, 1, 84));
+    assert_eq!(Colormap::RealPart.complex(zero), Color::white());
+}
+
+#[test]
+fn phase_maps_agree_on_hues() {
+    // both hsl maps show a phase of zero in cyan at mid amplitude
+    let half = Complex::new(0.5, 0.0);
+    assert_eq!(Colormap::PhaseDark.complex(half), Color::new(0, 255, 255));
+    let light = Colormap::PhaseLight.complex(half.scale(2.0));
+    assert!(
+        light.r == 0 && light.g >= 254 && light.b == 255,
+        "{:?}",
+        light
+    );
+    let wheel = |phase: f32| Colormap::PhaseColorblind.complex(Complex::from_polar(1.0, phase));
+    assert_eq!(wheel(0.0), wheel(2.0 * PI));
+    assert_ne!(wheel(0.0), wheel(PI));
+}
+
+#[test]
+fn diverging_maps_follow_their_component() {
+    let value = Complex::new(-1.0, 1.0);
+    assert_eq!(Colormap::RealPart.complex(value), Color::new(33, 102, 172));
+    assert_eq!(
+        Colormap::ImaginaryPart.complex(value),
+        Color::new(178, 24, 43)
+    );
+    assert_eq!(Colormap::Magma.scalar(1.0), Color::new(252, 253, 191));
+    assert_eq!(Colormap::Cividis.scalar(0.5), Color::new(112, 113, 115));
+}
@@ -1,22 +1,36 @@
 use color::Color;
-use colorsys::*;
-use std::f32::consts::PI;
+use colormap::Colormap;
+use scaling::Scaling;
 use std::fmt;
+use wasm_bindgen::prelude::*;
 
+#[wasm_bindgen]
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Complex {
     pub re: f32,
     pub im: f32,
 }
 
+/// Constructors, exported to JavaScript.
+#[wasm_bindgen]
 impl Complex {
     pub fn zero() -> Complex {
         Complex { re: 0.0, im: 0.0 }
     }
 
-    pub fn new(re: f32, im: f32) -> Self {
+    pub fn new(re: f32, im: f32) -> Complex {
         Complex { re, im }
     }
+}
+
+impl Complex {
+    /// Multiply by a real factor.
+    pub fn scale(&self, factor: f32) -> Self {
+        Complex {
+            re: self.re * factor,
+            im: self.im * factor,
+        }
+    }
 
     #[allow(dead_code)]
     pub fn conj(&self) -> Self {
@@ -79,17 +93,9 @@ impl Complex {
         }
     }
 
+    /// Color with the default colormap and scaling.
     pub fn rgb(&self) -> Color {
-        let h = (((self.phi() * 180. / PI) + 360.) % 360.) as f64;
-        let s = if self.radius() == 0. { 0. } else { 100. };
-        let l = (100. - (self.radius() * 50.0)) as f64;
-        let hsl = Hsl::from((h, s, l));
-        let rgb: Rgb = Rgb::from(&hsl);
-        Color {
-            r: 255 - rgb.red() as u8,
-            g: 255 - rgb.green() as u8,
-            b: 255 - rgb.blue() as u8,
-        }
+        Colormap::default().complex(Scaling::default().apply(*self))
     }
 }
 
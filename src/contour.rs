@@ -0,0 +1,233 @@
+//! Vector linework traced from grids: isolines by marching squares and wall outlines.
+//! Points are in cell units, cell (x, y) covering [x, x + 1] × [y, y + 1].
+
+use grid::Grid;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// A line through points in cell units, closed when its ends coincide.
+pub type Polyline = Vec<(f32, f32)>;
+
+/// Edge of the marching squares between two neighbouring cell centers,
+/// horizontal or vertical, starting at cell (x, y).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Edge {
+    Horizontal(usize, usize),
+    Vertical(usize, usize),
+}
+
+/// Lines where the field crosses level, through squares joining four cell centers.
+/// Saddles are resolved by the average of the four corners.
+pub fn isolines(field: &Grid<f32>, level: f32) -> Vec<Polyline> {
+    let value = |x: usize, y: usize| field.data[x + y * field.width];
+    let above = |v: f32| v > level;
+    let mut segments = Vec::new();
+    for y in 0..field.height.saturating_sub(1) {
+        for x in 0..field.width.saturating_sub(1) {
+            let corners = [
+                value(x, y),
+                value(x + 1, y),
+                value(x + 1, y + 1),
+                value(x, y + 1),
+            ];
+            // edges around the square: top, right, bottom and left
+            let edges = [
+                Edge::Horizontal(x, y),
+                Edge::Vertical(x + 1, y),
+                Edge::Horizontal(x, y + 1),
+                Edge::Vertical(x, y),
+            ];
+            let crossed: Vec<Edge> = (0..4)
+                .filter(|&i| above(corners[i]) != above(corners[(i + 1) % 4]))
+                .map(|i| edges[i])
+                .collect();
+            match crossed.len() {
+                2 => segments.push((crossed[0], crossed[1])),
+                4 => {
+                    let center = corners.iter().sum::<f32>() / 4.0;
+                    if above(center) == above(corners[0]) {
+                        // top left joins bottom right, cutting off the two other corners
+                        segments.push((edges[0], edges[1]));
+                        segments.push((edges[2], edges[3]));
+                    } else {
+                        segments.push((edges[3], edges[0]));
+                        segments.push((edges[1], edges[2]));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    join(&segments)
+        .into_iter()
+        .map(|line| {
+            line.into_iter()
+                .map(|edge| crossing(field, level, edge))
+                .collect()
+        })
+        .collect()
+}
+
+/// Point of an edge where the field crosses level, linearly interpolated between
+/// the cell centers, or midway when a value is not finite.
+fn crossing(field: &Grid<f32>, level: f32, edge: Edge) -> (f32, f32) {
+    let value = |x: usize, y: usize| field.data[x + y * field.width];
+    let (x, y, a, b) = match edge {
+        Edge::Horizontal(x, y) => (x, y, value(x, y), value(x + 1, y)),
+        Edge::Vertical(x, y) => (x, y, value(x, y), value(x, y + 1)),
+    };
+    let t = (level - a) / (b - a);
+    let t = if t.is_finite() {
+        t.clamp(0.0, 1.0)
+    } else {
+        0.5
+    };
+    let (cx, cy) = (x as f32 + 0.5, y as f32 + 0.5);
+    match edge {
+        Edge::Horizontal(..) => (cx + t, cy),
+        Edge::Vertical(..) => (cx, cy + t),
+    }
+}
+
+/// Borders between set and unset cells, outside the grid counting as unset.
+/// Straight runs are merged, so a rectangle gives its four corners and the first again.
+pub fn outlines(mask: &Grid<bool>) -> Vec<Polyline> {
+    let set = |x: i32, y: i32| {
+        x >= 0
+            && y >= 0
+            && (x as usize) < mask.width
+            && (y as usize) < mask.height
+            && mask.data[x as usize + y as usize * mask.width]
+    };
+    let mut segments = Vec::new();
+    for y in 0..=mask.height as i32 {
+        for x in 0..=mask.width as i32 {
+            if set(x, y) != set(x, y - 1) {
+                segments.push(((x, y), (x + 1, y)));
+            }
+            if set(x, y) != set(x - 1, y) {
+                segments.push(((x, y), (x, y + 1)));
+            }
+        }
+    }
+    join(&segments)
+        .into_iter()
+        .map(|line| {
+            let kept = (0..line.len()).filter(|&i| {
+                i == 0 || i + 1 == line.len() || {
+                    let (a, b, c) = (line[i - 1], line[i], line[i + 1]);
+                    (b.0 - a.0) * (c.1 - b.1) != (b.1 - a.1) * (c.0 - b.0)
+                }
+            });
+            kept.map(|i| (line[i].0 as f32, line[i].1 as f32)).collect()
+        })
+        .collect()
+}
+
+/// Chain segments sharing ends into lines, starting from loose ends so open lines stay whole.
+fn join<K: Copy + Eq + Hash>(segments: &[(K, K)]) -> Vec<Vec<K>> {
+    let mut ends: HashMap<K, Vec<usize>> = HashMap::new();
+    for (i, &(a, b)) in segments.iter().enumerate() {
+        ends.entry(a).or_default().push(i);
+        ends.entry(b).or_default().push(i);
+    }
+    let mut starts = Vec::new();
+    for (i, &(a, b)) in segments.iter().enumerate() {
+        if ends[&a].len() == 1 {
+            starts.push((a, b, i));
+        } else if ends[&b].len() == 1 {
+            starts.push((b, a, i));
+        }
+    }
+    starts.extend(segments.iter().enumerate().map(|(i, &(a, b))| (a, b, i)));
+
+    let mut used = vec![false; segments.len()];
+    let mut lines = Vec::new();
+    for (start, next, i) in starts {
+        if used[i] {
+            continue;
+        }
+        used[i] = true;
+        let mut line = vec![start, next];
+        let mut tip = next;
+        while let Some(&j) = ends[&tip].iter().find(|&&j| !used[j]) {
+            used[j] = true;
+            let (a, b) = segments[j];
+            tip = if a == tip { b } else { a };
+            line.push(tip);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+#[test]
+fn isoline_of_a_cone_is_a_closed_ring() {
+    let mut field = Grid::<f32>::new(21, 21);
+    for y in 0..21 {
+        for x in 0..21 {
+            let (dx, dy) = (x as f32 - 10.0, y as f32 - 10.0);
+            field.data[x + y * 21] = (dx * dx + dy * dy).sqrt();
+        }
+    }
+    let lines = isolines(&field, 6.0);
+    assert_eq!(lines.len(), 1);
+    let ring = &lines[0];
+    assert_eq!(ring.first(), ring.last());
+    for &(x, y) in ring {
+        let radius = ((x - 10.5).powi(2) + (y - 10.5).powi(2)).sqrt();
+        assert!((radius - 6.0).abs() < 0.1, "{}", radius);
+    }
+    assert!(isolines(&field, 100.0).is_empty());
+}
+
+#[test]
+fn isolines_open_at_the_border_and_split_saddles() {
+    let mut ramp = Grid::<f32>::new(4, 3);
+    for (i, value) in ramp.data.iter_mut().enumerate() {
+        *value = (i % 4) as f32;
+    }
+    let lines = isolines(&ramp, 1.5);
+    assert_eq!(lines, vec![vec![(2.0, 0.5), (2.0, 1.5), (2.0, 2.5)]]);
+
+    let saddle = Grid {
+        width: 2,
+        height: 2,
+        data: vec![1.0, 0.0, 0.0, 1.0],
+    };
+    let assert_lines = |level: f32, expected: &[[(f32, f32); 2]]| {
+        let lines = isolines(&saddle, level);
+        assert_eq!(lines.len(), expected.len());
+        for (line, ends) in lines.iter().zip(expected.iter()) {
+            assert_eq!(line.len(), 2);
+            for (&(x, y), &(ex, ey)) in line.iter().zip(ends.iter()) {
+                assert!(
+                    (x - ex).abs() < 1e-5 && (y - ey).abs() < 1e-5,
+                    "{:?}",
+                    lines
+                );
+            }
+        }
+    };
+    // above the center value the lines cut off the high corners, below it the low ones
+    assert_lines(0.6, &[[(0.5, 0.9), (0.9, 0.5)], [(1.5, 1.1), (1.1, 1.5)]]);
+    assert_lines(0.4, &[[(1.1, 0.5), (1.5, 0.9)], [(0.9, 1.5), (0.5, 1.1)]]);
+}
+
+#[test]
+fn outline_of_a_wall_block() {
+    let mut walls = Grid::<bool>::new(5, 4);
+    for y in 1..3 {
+        for x in 1..4 {
+            walls.data[x + y * 5] = true;
+        }
+    }
+    let lines = outlines(&walls);
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0].len(), 5);
+    assert_eq!(lines[0].first(), lines[0].last());
+    for corner in [(1.0, 1.0), (4.0, 1.0), (4.0, 3.0), (1.0, 3.0)].iter() {
+        assert!(lines[0].contains(corner));
+    }
+}
@@ -0,0 +1,160 @@
+//! Discrete Fourier transforms of complex grids.
+//! Power of two lengths use a radix-2 FFT, other lengths fall back to a direct DFT.
+
+use complex::Complex;
+use coord::Coord;
+use grid::Grid;
+use std::f32::consts::PI;
+
+/// In-place unnormalized transform; the inverse uses the positive exponent.
+pub fn fft(data: &mut [Complex], inverse: bool) {
+    let n = data.len();
+    if n <= 1 {
+        return;
+    }
+    let sign = if inverse { 1.0 } else { -1.0 };
+    if !n.is_power_of_two() {
+        let input = data.to_vec();
+        for (k, out) in data.iter_mut().enumerate() {
+            let mut sum = Complex::zero();
+            for (j, value) in input.iter().enumerate() {
+                let angle = sign * 2.0 * PI * ((j * k) % n) as f32 / n as f32;
+                sum = sum.add(&value.mul(&Complex::from_polar(1.0, angle)));
+            }
+            *out = sum;
+        }
+        return;
+    }
+
+    // bit reversal permutation
+    let bits = n.trailing_zeros();
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if j > i {
+            data.swap(i, j);
+        }
+    }
+    let mut len = 2;
+    while len <= n {
+        let step = Complex::from_polar(1.0, sign * 2.0 * PI / len as f32);
+        for start in (0..n).step_by(len) {
+            let mut w = Complex::new(1.0, 0.0);
+            for k in 0..len / 2 {
+                let even = data[start + k];
+                let odd = data[start + k + len / 2].mul(&w);
+                data[start + k] = even.add(&odd);
+                data[start + k + len / 2] = even.sub(&odd);
+                w = w.mul(&step);
+            }
+        }
+        len *= 2;
+    }
+}
+
+/// Unitary 2D transform of a grid, rows then columns.
+pub fn fft2(grid: &Grid<Complex>, inverse: bool) -> Grid<Complex> {
+    let (width, height) = (grid.width, grid.height);
+    let mut out = grid.clone();
+    for row in out.data.chunks_mut(width) {
+        fft(row, inverse);
+    }
+    let mut column = vec![Complex::zero(); height];
+    for x in 0..width {
+        for (y, value) in column.iter_mut().enumerate() {
+            *value = out.data[x + y * width];
+        }
+        fft(&mut column, inverse);
+        for (y, value) in column.iter().enumerate() {
+            out.data[x + y * width] = *value;
+        }
+    }
+    out.scale(Complex::new(1.0 / ((width * height) as f32).sqrt(), 0.0));
+    out
+}
+
+/// Move the zero frequency to the center of the grid, or back when inverse.
+pub fn shift(grid: &Grid<Complex>, inverse: bool) -> Grid<Complex> {
+    let (width, height) = (grid.width, grid.height);
+    let mut out = Grid::<Complex>::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let (sx, sy) = if inverse {
+                (
+                    (x + width - width / 2) % width,
+                    (y + height - height / 2) % height,
+                )
+            } else {
+                ((x + width / 2) % width, (y + height / 2) % height)
+            };
+            out.data[sx + sy * width] = grid.data[x + y * width];
+        }
+    }
+    out
+}
+
+/// Multiply by a separable Hann window to reduce leakage from the grid edges.
+pub fn hann_window(grid: &Grid<Complex>) -> Grid<Complex> {
+    let hann = |i: usize, n: usize| 0.5 - 0.5 * (2.0 * PI * i as f32 / (n - 1).max(1) as f32).cos();
+    let mut out = grid.clone();
+    for y in 0..grid.height {
+        for x in 0..grid.width {
+            let coord = Coord::new(x as i32, y as i32);
+            let weight = hann(x, grid.width) * hann(y, grid.height);
+            out.set(coord, grid.get(coord).unwrap().scale(weight));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+fn assert_close(a: Complex, b: Complex) {
+    assert!(a.sub(&b).radius() < 1e-4, "{} != {}", a, b);
+}
+
+#[cfg(test)]
+#[test]
+fn fft_of_a_delta_is_flat() {
+    for &n in [8, 6].iter() {
+        let mut data = vec![Complex::zero(); n];
+        data[0] = Complex::new(1.0, 0.0);
+        fft(&mut data, false);
+        for value in data.iter() {
+            assert_close(*value, Complex::new(1.0, 0.0));
+        }
+    }
+}
+
+#[test]
+fn fft_matches_direct_dft() {
+    let input: Vec<Complex> = (0..8)
+        .map(|i| Complex::new((i as f32).sin(), (i * i) as f32 / 10.0))
+        .collect();
+    let mut fast = input.clone();
+    fft(&mut fast, false);
+    for (k, value) in fast.iter().enumerate() {
+        let mut sum = Complex::zero();
+        for (j, x) in input.iter().enumerate() {
+            let angle = -2.0 * PI * (j * k) as f32 / 8.0;
+            sum = sum.add(&x.mul(&Complex::from_polar(1.0, angle)));
+        }
+        assert_close(*value, sum);
+    }
+}
+
+#[test]
+fn fft2_round_trip_and_shift() {
+    let mut grid = Grid::<Complex>::new(8, 6);
+    for (i, cell) in grid.data.iter_mut().enumerate() {
+        *cell = Complex::from_polar(1.0, 2.0 * PI * (i % 8) as f32 / 8.0);
+    }
+    let spectrum = fft2(&grid, false);
+    // a plane wave with one cycle along x lands in the (1, 0) bin, at (5, 3) once centered
+    assert!((spectrum.get(Coord::new(1, 0)).unwrap().norm() - 48.0).abs() < 1e-3);
+    let centered = shift(&spectrum, false);
+    assert!((centered.get(Coord::new(5, 3)).unwrap().norm() - 48.0).abs() < 1e-3);
+
+    let back = fft2(&shift(&centered, true), true);
+    for (a, b) in back.data.iter().zip(grid.data.iter()) {
+        assert_close(*a, *b);
+    }
+}
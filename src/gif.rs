@@ -0,0 +1,330 @@
+//! Minimal animated GIF encoder.
+//! Every frame gets its own 256 color table from a median cut of its pixels,
+//! and the color indices are LZW compressed as the format requires.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io;
+
+/// Codes are at most 12 bits long.
+const MAX_CODES: u16 = 4096;
+/// Color indices take 8 bits, so the clear code is 256 and the end code 257.
+const MIN_CODE_SIZE: u8 = 8;
+
+/// Width and height in the 16 bit little endian fields of the format.
+fn size_fields(width: usize, height: usize) -> io::Result<[u8; 4]> {
+    let field = |n: usize| {
+        u16::try_from(n).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "GIF frames are at most 65535 pixels wide and high",
+            )
+        })
+    };
+    let (w, h) = (field(width)?.to_le_bytes(), field(height)?.to_le_bytes());
+    Ok([w[0], w[1], h[0], h[1]])
+}
+
+/// GIF89a header for width by height frames looping forever.
+/// Sizes the format cannot hold are an error and leave out untouched.
+pub fn write_header(out: &mut Vec<u8>, width: usize, height: usize) -> io::Result<()> {
+    let size = size_fields(width, height)?;
+    out.extend_from_slice(b"GIF89a");
+    out.extend_from_slice(&size);
+    // no global color table, background 0, square pixels
+    out.extend_from_slice(&[0, 0, 0]);
+    out.extend_from_slice(&[0x21, 0xff, 11]);
+    out.extend_from_slice(b"NETSCAPE2.0");
+    out.extend_from_slice(&[3, 1, 0, 0, 0]);
+    Ok(())
+}
+
+/// Frame of r, g, b, a pixels shown for delay hundredths of a second.
+/// Sizes the format cannot hold are an error and leave out untouched.
+pub fn write_frame(
+    out: &mut Vec<u8>,
+    width: usize,
+    height: usize,
+    rgba: &[u8],
+    delay: u16,
+) -> io::Result<()> {
+    let size = size_fields(width, height)?;
+    let pixels: Vec<[u8; 3]> = rgba.chunks(4).map(|p| [p[0], p[1], p[2]]).collect();
+    let (palette, indices) = quantize(&pixels);
+
+    // graphic control extension: leave the frame in place, no transparency
+    out.extend_from_slice(&[0x21, 0xf9, 4, 0x04]);
+    out.extend_from_slice(&delay.to_le_bytes());
+    out.extend_from_slice(&[0, 0]);
+
+    // image descriptor at the origin with a local table of 256 colors
+    out.push(0x2c);
+    out.extend_from_slice(&[0, 0, 0, 0]);
+    out.extend_from_slice(&size);
+    out.push(0x80 | 7);
+    for i in 0..256 {
+        out.extend_from_slice(&palette.get(i).cloned().unwrap_or([0, 0, 0]));
+    }
+
+    out.push(MIN_CODE_SIZE);
+    for block in lzw(&indices).chunks(255) {
+        out.push(block.len() as u8);
+        out.extend_from_slice(block);
+    }
+    out.push(0);
+    Ok(())
+}
+
+pub fn write_trailer(out: &mut Vec<u8>) {
+    out.push(0x3b);
+}
+
+/// Reduce the colors to at most 256 by median cut, returning the palette and the index
+/// of every pixel in it.
+fn quantize(pixels: &[[u8; 3]]) -> (Vec<[u8; 3]>, Vec<u8>) {
+    let mut counts: HashMap<[u8; 3], u32> = HashMap::new();
+    for &pixel in pixels {
+        *counts.entry(pixel).or_insert(0) += 1;
+    }
+    let mut boxes: Vec<Vec<([u8; 3], u32)>> = vec![counts.into_iter().collect()];
+    while boxes.len() < 256 {
+        // split the box with the widest channel range at its weighted median
+        let (index, channel, range) = boxes
+            .iter()
+            .enumerate()
+            .map(|(i, colors)| {
+                let (channel, range) = widest_channel(colors);
+                (i, channel, range)
+            })
+            .max_by_key(|&(_, _, range)| range)
+            .unwrap();
+        if range == 0 {
+            break;
+        }
+        let mut colors = boxes.swap_remove(index);
+        colors.sort_by_key(|&(color, _)| color[channel]);
+        let total: u32 = colors.iter().map(|&(_, count)| count).sum();
+        let mut seen = 0;
+        let mut split = colors.len() - 1;
+        for (i, &(_, count)) in colors.iter().enumerate() {
+            seen += count;
+            if 2 * seen >= total {
+                split = (i + 1).min(colors.len() - 1);
+                break;
+            }
+        }
+        let upper = colors.split_off(split);
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+
+    let mut palette = Vec::with_capacity(boxes.len());
+    let mut lookup: HashMap<[u8; 3], u8> = HashMap::new();
+    for (i, colors) in boxes.iter().enumerate() {
+        let total: u32 = colors.iter().map(|&(_, count)| count).sum();
+        let mut sum = [0u32; 3];
+        for &(color, count) in colors {
+            for c in 0..3 {
+                sum[c] += color[c] as u32 * count;
+            }
+            lookup.insert(color, i as u8);
+        }
+        let average = |c: usize| ((sum[c] + total / 2) / total.max(1)) as u8;
+        palette.push([average(0), average(1), average(2)]);
+    }
+    let indices = pixels.iter().map(|pixel| lookup[pixel]).collect();
+    (palette, indices)
+}
+
+/// Channel with the largest spread in a set of colors, along with that spread.
+fn widest_channel(colors: &[([u8; 3], u32)]) -> (usize, u8) {
+    (0..3)
+        .map(|c| {
+            let min = colors.iter().map(|&(color, _)| color[c]).min().unwrap_or(0);
+            let max = colors.iter().map(|&(color, _)| color[c]).max().unwrap_or(0);
+            (c, max - min)
+        })
+        .max_by_key(|&(_, range)| range)
+        .unwrap()
+}
+
+/// Variable length LZW compression of 8 bit indices, codes packed from the lowest bit.
+fn lzw(indices: &[u8]) -> Vec<u8> {
+    let clear = 1u16 << MIN_CODE_SIZE;
+    let end = clear + 1;
+    let mut writer = BitWriter::default();
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut code_size = MIN_CODE_SIZE + 1;
+    let mut next_code = end + 1;
+    writer.write(clear, code_size);
+
+    let mut iter = indices.iter();
+    let mut prefix = match iter.next() {
+        Some(&index) => index as u16,
+        None => {
+            writer.write(end, code_size);
+            return writer.finish();
+        }
+    };
+    for &index in iter {
+        if let Some(&code) = table.get(&(prefix, index)) {
+            prefix = code;
+            continue;
+        }
+        writer.write(prefix, code_size);
+        // the decoder widens its codes once its table reaches the current size
+        if next_code >= 1 << code_size && code_size < 12 {
+            code_size += 1;
+        }
+        if next_code < MAX_CODES {
+            table.insert((prefix, index), next_code);
+            next_code += 1;
+        } else {
+            writer.write(clear, code_size);
+            table.clear();
+            code_size = MIN_CODE_SIZE + 1;
+            next_code = end + 1;
+        }
+        prefix = index as u16;
+    }
+    writer.write(prefix, code_size);
+    if next_code >= 1 << code_size && code_size < 12 {
+        code_size += 1;
+    }
+    writer.write(end, code_size);
+    writer.finish()
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+/// Reference decoder following the GIF specification, to check the encoder against.
+#[cfg(test)]
+fn lzw_decode(data: &[u8]) -> Vec<u8> {
+    let clear = 1u16 << MIN_CODE_SIZE;
+    let end = clear + 1;
+    let (mut position, mut output) = (0usize, Vec::new());
+    let mut read = |size: u8| {
+        let mut code = 0u16;
+        for i in 0..size as usize {
+            let bit = (data[(position + i) / 8] >> ((position + i) % 8)) & 1;
+            code |= (bit as u16) << i;
+        }
+        position += size as usize;
+        code
+    };
+    let mut table: Vec<Vec<u8>> = Vec::new();
+    let mut code_size = MIN_CODE_SIZE + 1;
+    let mut previous: Option<Vec<u8>> = None;
+    loop {
+        let code = read(code_size);
+        if code == clear {
+            table = (0..clear).map(|i| vec![i as u8]).collect();
+            table.push(Vec::new());
+            table.push(Vec::new());
+            code_size = MIN_CODE_SIZE + 1;
+            previous = None;
+            continue;
+        }
+        if code == end {
+            return output;
+        }
+        let entry = match previous {
+            None => table[code as usize].clone(),
+            Some(ref previous) => {
+                let entry = if (code as usize) < table.len() {
+                    table[code as usize].clone()
+                } else {
+                    let mut entry = previous.clone();
+                    entry.push(previous[0]);
+                    entry
+                };
+                if table.len() < MAX_CODES as usize {
+                    let mut added = previous.clone();
+                    added.push(entry[0]);
+                    table.push(added);
+                    if table.len() == 1 << code_size && code_size < 12 {
+                        code_size += 1;
+                    }
+                }
+                entry
+            }
+        };
+        output.extend_from_slice(&entry);
+        previous = Some(entry);
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn lzw_round_trip() {
+    assert_eq!(lzw_decode(&lzw(&[])), Vec::<u8>::new());
+    let repetitive: Vec<u8> = (0..5000).map(|i| (i / 7 % 3) as u8).collect();
+    assert_eq!(lzw_decode(&lzw(&repetitive)), repetitive);
+    // enough distinct strings to fill the table and force clear codes
+    let mut state = 12345u32;
+    let noisy: Vec<u8> = (0..40000)
+        .map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (state >> 16) as u8
+        })
+        .collect();
+    assert_eq!(lzw_decode(&lzw(&noisy)), noisy);
+}
+
+#[test]
+fn quantize_keeps_few_colors_exact() {
+    let pixels = vec![[255, 0, 0], [0, 0, 255], [255, 0, 0], [10, 200, 30]];
+    let (palette, indices) = quantize(&pixels);
+    assert_eq!(palette.len(), 3);
+    for (pixel, &index) in pixels.iter().zip(indices.iter()) {
+        assert_eq!(&palette[index as usize], pixel);
+    }
+    let gradient: Vec<[u8; 3]> = (0..1000)
+        .map(|i| [(i % 256) as u8, (i / 4) as u8, 7])
+        .collect();
+    assert_eq!(quantize(&gradient).0.len(), 256);
+}
+
+#[test]
+fn write_an_animation() {
+    let mut out = Vec::new();
+    write_header(&mut out, 2, 1).unwrap();
+    write_frame(&mut out, 2, 1, &[255, 0, 0, 255, 0, 0, 255, 255], 4).unwrap();
+    write_trailer(&mut out);
+    assert_eq!(&out[..6], b"GIF89a");
+    assert_eq!(&out[6..10], &[2, 0, 1, 0]);
+    assert_eq!(out.last(), Some(&0x3b));
+}
+
+#[test]
+fn reject_frames_too_large_for_the_format() {
+    let mut out = Vec::new();
+    assert!(write_header(&mut out, 65536, 1).is_err());
+    assert!(write_frame(&mut out, 1, 70000, &[], 4).is_err());
+    assert!(out.is_empty());
+    assert!(write_header(&mut out, 65535, 65535).is_ok());
+}
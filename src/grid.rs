@@ -1,8 +1,10 @@
 extern crate colored;
 use color::Color;
 use colored::*;
+use colormap::Colormap;
 use complex::Complex;
 use coord::Coord;
+use scaling::Scaling;
 use std::fmt;
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -22,12 +24,30 @@ impl Grid<bool> {
             data: vec![false; width * height],
         }
     }
+
+    /// Returns the top left and bottom right corners enclosing all set cells.
+    pub fn bounds(&self) -> Option<(Coord, Coord)> {
+        let mut bounds: Option<(Coord, Coord)> = None;
+        for (i, &cell) in self.data.iter().enumerate() {
+            if cell {
+                let coord = Coord::new((i % self.width) as i32, (i / self.width) as i32);
+                bounds = Some(match bounds {
+                    None => (coord, coord),
+                    Some((tl, br)) => (
+                        Coord::new(tl.x.min(coord.x), tl.y.min(coord.y)),
+                        Coord::new(br.x.max(coord.x), br.y.max(coord.y)),
+                    ),
+                });
+            }
+        }
+        bounds
+    }
 }
 
 /// Implement display for the cells
 impl fmt::Display for Grid<bool> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for line in self.data.as_slice().chunks(self.width as usize) {
+        for line in self.data.as_slice().chunks(self.width) {
             for &cell in line {
                 let symbol = if cell { '◻' } else { '◼' };
                 write!(f, "{}", symbol)?;
@@ -63,25 +83,34 @@ impl Grid<f32> {
     pub fn max(&self) -> f32 {
         self.data.iter().cloned().fold(0.0, |a, b| a.max(b))
     }
+
+    /// Convert to rgb colors with the given colormap, the scaling mode reaching its full level
+    /// at the largest magnitude. Density maps span the range from the minimum instead.
+    pub fn colors(&self, colormap: Colormap, scaling: &Scaling) -> Grid<Color> {
+        let min = self.data.iter().cloned().fold(f32::INFINITY, f32::min);
+        let max = self.data.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+        let (offset, range) = if colormap.is_density() {
+            (min, max - min)
+        } else {
+            (0.0, max.abs().max(min.abs()))
+        };
+        let scaling = scaling.with_max(range);
+        Grid {
+            width: self.width,
+            height: self.height,
+            data: self
+                .data
+                .iter()
+                .map(|&v| colormap.scalar(scaling.signed(v - offset)))
+                .collect(),
+        }
+    }
 }
 
 /// Implement display for the cells
 impl fmt::Display for Grid<f32> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for line in self.data.as_slice().chunks(self.width as usize) {
-            for &cell in line {
-                let rgb = (127. + cell * 127.0) as u8;
-                if cell == 0.0 {
-                    let symbol = "◼".black();
-                    write!(f, "{}", symbol)?;
-                } else {
-                    let symbol = "◼".truecolor(rgb, rgb, rgb);
-                    write!(f, "{}", symbol)?;
-                }
-            }
-            writeln!(f)?;
-        }
-        Ok(())
+        self.colored(Colormap::default()).fmt(f)
     }
 }
 
@@ -106,14 +135,17 @@ impl Grid<Complex> {
         }
     }
 
-    #[allow(dead_code)]
-    /// Convert quantum to rgb colors.
-    pub fn into_cells(self) -> Grid<Color> {
-        let mut colors: Grid<Color> = Grid::<Color>::new(self.width, self.height);
-        for (i, c) in self.data.iter().enumerate() {
-            colors.data[i] = c.rgb();
+    /// Convert quantum to rgb colors with the given colormap and amplitude scaling.
+    pub fn colors(&self, colormap: Colormap, scaling: &Scaling) -> Grid<Color> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            data: self
+                .data
+                .iter()
+                .map(|&c| colormap.complex(scaling.apply(c)))
+                .collect(),
         }
-        colors
     }
 
     /// Reset to default values.
@@ -121,20 +153,105 @@ impl Grid<Complex> {
         let size = self.data.len();
         self.data = vec![Complex::zero(); size];
     }
+
+    /// Total probability, the sum of squared moduli.
+    pub fn norm(&self) -> f32 {
+        self.data.iter().map(|c| c.norm()).sum()
+    }
+
+    /// Multiply every cell by a complex factor.
+    pub fn scale(&mut self, factor: Complex) {
+        for cell in self.data.iter_mut() {
+            *cell = cell.mul(&factor);
+        }
+    }
+
+    /// Scale the field so that its total probability is the target, leaving a null field untouched.
+    pub fn normalize_to(&mut self, target: f32) {
+        let norm = self.norm();
+        if norm > 0.0 {
+            self.scale(Complex::new((target / norm).sqrt(), 0.0));
+        }
+    }
+
+    /// Probability current density j = Im(ψ* ∇ψ) with central differences.
+    /// Border cells and cells selected by the walls mask carry no current.
+    pub fn current(&self, walls: &Grid<bool>) -> Grid<(f32, f32)> {
+        let mut current = Grid::<(f32, f32)>::new(self.width, self.height);
+        for y in 1..self.height.saturating_sub(1) {
+            for x in 1..self.width.saturating_sub(1) {
+                let coord = Coord::new(x as i32, y as i32);
+                if !*walls.get(coord).unwrap() {
+                    let conj = self.get(coord).unwrap().conj();
+                    let top = self.get(coord.top()).unwrap();
+                    let bottom = self.get(coord.bottom()).unwrap();
+                    let left = self.get(coord.left()).unwrap();
+                    let right = self.get(coord.right()).unwrap();
+                    let jx = 0.5 * conj.mul(&right.sub(left)).im;
+                    let jy = 0.5 * conj.mul(&bottom.sub(top)).im;
+                    current.set(coord, (jx, jy));
+                }
+            }
+        }
+        current
+    }
+
+    /// Set to zero every cell selected by the mask, matched by coordinate.
+    /// Mask cells outside the grid are ignored.
+    pub fn clear(&mut self, mask: &Grid<bool>) {
+        for y in 0..mask.height {
+            for x in 0..mask.width {
+                let coord = Coord::new(x as i32, y as i32);
+                if *mask.get(coord).unwrap() && self.is_valid_coord(&coord) {
+                    self.set(coord, Complex::zero());
+                }
+            }
+        }
+    }
 }
 
 /// Implement display for the cells
 impl fmt::Display for Grid<Complex> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for line in self.data.as_slice().chunks(self.width as usize) {
-            for &complex in line {
-                let color = complex.rgb();
-                let symbol = "◼".truecolor(color.r, color.g, color.b);
-                write!(f, "{}", symbol)?;
-            }
-            writeln!(f)?;
+        self.colored(Colormap::default()).fmt(f)
+    }
+}
+
+/// Terminal display of a grid with a chosen colormap.
+pub struct Colored<'a, T: 'a> {
+    grid: &'a Grid<T>,
+    colormap: Colormap,
+    scaling: Scaling,
+}
+
+impl<'a, T> Colored<'a, T> {
+    /// Use another amplitude scaling; real grids only keep its mode.
+    pub fn scaled(mut self, scaling: Scaling) -> Self {
+        self.scaling = scaling;
+        self
+    }
+}
+
+/// Print one colored square per cell.
+fn write_colors(f: &mut fmt::Formatter, colors: &Grid<Color>) -> fmt::Result {
+    for line in colors.data.as_slice().chunks(colors.width) {
+        for color in line {
+            write!(f, "{}", "◼".truecolor(color.r, color.g, color.b))?;
         }
-        Ok(())
+        writeln!(f)?;
+    }
+    Ok(())
+}
+
+impl<'a> fmt::Display for Colored<'a, Complex> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_colors(f, &self.grid.colors(self.colormap, &self.scaling))
+    }
+}
+
+impl<'a> fmt::Display for Colored<'a, f32> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_colors(f, &self.grid.colors(self.colormap, &self.scaling))
     }
 }
 
@@ -148,10 +265,79 @@ impl Grid<Color> {
             data: vec![Color::white(); width * height],
         }
     }
+
+    /// Enlarge each cell to a square of factor by factor pixels.
+    pub fn upscale(&self, factor: usize) -> Grid<Color> {
+        let mut pixels = Grid::<Color>::new(self.width * factor, self.height * factor);
+        for y in 0..pixels.height {
+            for x in 0..pixels.width {
+                pixels.data[x + y * pixels.width] = self.data[x / factor + y / factor * self.width];
+            }
+        }
+        pixels
+    }
+
+    /// Draw a straight line between two points, clipped to the grid.
+    pub fn draw_line(&mut self, from: (f32, f32), to: (f32, f32), color: Color) {
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let steps = dx.abs().max(dy.abs()).ceil().max(1.0) as usize;
+        for i in 0..=steps {
+            let t = i as f32 / steps as f32;
+            let x = (from.0 + dx * t).round() as i32;
+            let y = (from.1 + dy * t).round() as i32;
+            let coord = Coord::new(x, y);
+            if self.is_valid_coord(&coord) {
+                self.set(coord, color);
+            }
+        }
+    }
+
+    /// Flatten to opaque r, g, b, a bytes, row after row, as expected by ImageData.
+    pub fn rgba_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.data.len() * 4);
+        self.write_rgba(&mut bytes);
+        bytes
+    }
+
+    /// Flatten into an existing buffer, reusing its allocation when it is large enough.
+    pub fn write_rgba(&self, bytes: &mut Vec<u8>) {
+        bytes.resize(self.data.len() * 4, 255);
+        for (pixel, color) in bytes.chunks_mut(4).zip(self.data.iter()) {
+            pixel.copy_from_slice(&[color.r, color.g, color.b, 255]);
+        }
+    }
+}
+
+/// Specific methods for vector grids.
+impl Grid<(f32, f32)> {
+    /// Create a new grid of the given width and height.
+    pub fn new(width: usize, height: usize) -> Self {
+        Grid {
+            width,
+            height,
+            data: vec![(0.0, 0.0); width * height],
+        }
+    }
+
+    /// Largest vector length in the grid.
+    pub fn max_length(&self) -> f32 {
+        self.data
+            .iter()
+            .fold(0.0, |max, &(x, y)| f32::max(max, x.hypot(y)))
+    }
 }
 
 /// Generic grid methods.
 impl<T> Grid<T> {
+    /// Display the grid in the terminal with the given colormap.
+    pub fn colored(&self, colormap: Colormap) -> Colored<'_, T> {
+        Colored {
+            grid: self,
+            colormap,
+            scaling: Scaling::default(),
+        }
+    }
+
     /// Returns the value at the given coord.
     pub fn get(&self, coord: Coord) -> Option<&T> {
         if self.is_valid_coord(&coord) {
@@ -204,3 +390,62 @@ fn returns_an_option_for_f32_grid_get_coord() {
     assert_eq!(grid.get(valid), Some(&0.0));
     assert_eq!(grid.get(invalid), None);
 }
+
+#[test]
+fn bounds_of_a_bool_grid() {
+    let mut grid: Grid<bool> = Grid::<bool>::new(8, 5);
+    assert_eq!(grid.bounds(), None);
+    grid.set(Coord::new(5, 1), true);
+    grid.set(Coord::new(2, 3), true);
+    assert_eq!(grid.bounds(), Some((Coord::new(2, 1), Coord::new(5, 3))));
+}
+
+#[test]
+fn normalize_a_complex_grid() {
+    let mut grid: Grid<Complex> = Grid::<Complex>::new(2, 2);
+    grid.set(Coord::new(0, 0), Complex::new(3.0, 0.0));
+    grid.set(Coord::new(1, 1), Complex::new(0.0, 4.0));
+    assert_eq!(grid.norm(), 25.0);
+    grid.normalize_to(1.0);
+    assert!((grid.norm() - 1.0).abs() < 1e-6);
+    assert_eq!(grid.get(Coord::new(0, 0)), Some(&Complex::new(0.6, 0.0)));
+
+    let mut mask: Grid<bool> = Grid::<bool>::new(2, 2);
+    mask.set(Coord::new(0, 0), true);
+    grid.clear(&mask);
+    assert!((grid.norm() - 0.64).abs() < 1e-6);
+
+    // a mask of another size clears the cells at the same coordinates
+    grid.set(Coord::new(0, 1), Complex::new(0.6, 0.0));
+    let mut mask: Grid<bool> = Grid::<bool>::new(3, 2);
+    mask.set(Coord::new(2, 0), true);
+    grid.clear(&mask);
+    assert!((grid.norm() - 1.0).abs() < 1e-6);
+    mask.set(Coord::new(1, 1), true);
+    grid.clear(&mask);
+    assert!((grid.norm() - 0.36).abs() < 1e-6);
+}
+
+#[test]
+fn current_of_a_plane_wave() {
+    let mut grid: Grid<Complex> = Grid::<Complex>::new(5, 5);
+    for (i, cell) in grid.data.iter_mut().enumerate() {
+        *cell = Complex::from_polar(1.0, 0.3 * (i % 5) as f32);
+    }
+    let walls: Grid<bool> = Grid::<bool>::new(5, 5);
+    let current = grid.current(&walls);
+    let (jx, jy) = *current.get(Coord::new(2, 2)).unwrap();
+    assert!((jx - 0.3f32.sin()).abs() < 1e-6 && jy.abs() < 1e-6);
+    assert_eq!(current.get(Coord::new(0, 2)), Some(&(0.0, 0.0)));
+}
+
+#[test]
+fn draw_line_on_upscaled_colors() {
+    let colors: Grid<Color> = Grid::<Color>::new(2, 2);
+    let mut pixels = colors.upscale(3);
+    assert_eq!(pixels.data.len(), 36);
+    pixels.draw_line((0.0, 0.0), (5.0, 5.0), Color::new(0, 0, 0));
+    assert_eq!(pixels.get(Coord::new(3, 3)), Some(&Color::new(0, 0, 0)));
+    assert_eq!(pixels.get(Coord::new(3, 4)), Some(&Color::white()));
+    assert_eq!(pixels.rgba_bytes().len(), 144);
+}
@@ -0,0 +1,190 @@
+//! Legends matching the rendered colors: a phase wheel for complex maps
+//! and a labelled colorbar for real fields.
+
+use color::Color;
+use colormap::Colormap;
+use complex::Complex;
+use coord::Coord;
+use grid::Grid;
+use scaling::Scaling;
+
+/// Glyphs of 3 by 5 pixels, a row per byte with the leftmost pixel in the bit of value 4.
+const GLYPHS: [(char, [u8; 5]); 14] = [
+    ('0', [7, 5, 5, 5, 7]),
+    ('1', [2, 6, 2, 2, 7]),
+    ('2', [7, 1, 7, 4, 7]),
+    ('3', [7, 1, 7, 1, 7]),
+    ('4', [5, 5, 7, 1, 1]),
+    ('5', [7, 4, 7, 1, 7]),
+    ('6', [7, 4, 7, 5, 7]),
+    ('7', [7, 1, 1, 1, 1]),
+    ('8', [7, 5, 7, 5, 7]),
+    ('9', [7, 5, 7, 1, 7]),
+    ('.', [0, 0, 0, 0, 2]),
+    ('-', [0, 0, 7, 0, 0]),
+    ('+', [0, 2, 7, 2, 0]),
+    ('e', [2, 5, 7, 4, 3]),
+];
+
+/// Disk of size by size r, g, b, a pixels colored like the field, phase 0 to the right
+/// and turning counterclockwise, the amplitude growing from the center to the scaling
+/// maximum at the rim. Pixels outside the disk are transparent.
+pub fn wheel(colormap: Colormap, scaling: &Scaling, size: usize) -> Vec<u8> {
+    let radius = size as f32 / 2.0;
+    let mut disk = Grid::<Complex>::new(size, size);
+    let mut inside = vec![false; size * size];
+    for y in 0..size {
+        for x in 0..size {
+            let dx = (x as f32 + 0.5 - radius) / radius;
+            let dy = (radius - y as f32 - 0.5) / radius;
+            let r = (dx * dx + dy * dy).sqrt();
+            disk.data[x + y * size] = Complex::from_polar(r * scaling.max, dy.atan2(dx));
+            inside[x + y * size] = r <= 1.0;
+        }
+    }
+    let mut rgba = disk.colors(colormap, scaling).rgba_bytes();
+    for (pixel, &inside) in rgba.chunks_mut(4).zip(inside.iter()) {
+        if !inside {
+            pixel[3] = 0;
+        }
+    }
+    rgba
+}
+
+/// Colorbar width by height pixels showing a row of colors going from min to max,
+/// above tick marks and labels at both ends and in the middle. Empty when either size is zero.
+pub fn colorbar(
+    gradient: &Grid<Color>,
+    min: f32,
+    max: f32,
+    width: usize,
+    height: usize,
+) -> Grid<Color> {
+    if width == 0 || height == 0 {
+        return Grid::<Color>::new(width, height);
+    }
+    let scale = (height / 12).clamp(1, 4);
+    let labels_height = 7 * scale;
+    let tick = 2 * scale;
+    let bar = height.saturating_sub(labels_height + tick).max(1);
+    let mut pixels = Grid::<Color>::new(width, height);
+    for y in 0..bar.min(height) {
+        for x in 0..width {
+            let source = x * gradient.width / width.max(1);
+            pixels.data[x + y * width] = gradient.data[source.min(gradient.width - 1)];
+        }
+    }
+
+    let ink = Color::new(0, 0, 0);
+    let mid = (min + max) / 2.0;
+    let ticks = [
+        (0, min, 0.0),
+        (width / 2, mid, 0.5),
+        (width.saturating_sub(1), max, 1.0),
+    ];
+    for &(x, value, align) in ticks.iter() {
+        for y in bar..(bar + tick).min(height) {
+            pixels.data[x + y * width] = ink;
+        }
+        let text = label(value);
+        let text_width = (4 * text.len() - 1) * scale;
+        let left = (x as f32 + 1.0 - align * text_width as f32).max(0.0) as usize;
+        let left = left.min(width.saturating_sub(text_width));
+        draw_text(&mut pixels, &text, left, bar + tick + scale, scale, ink);
+    }
+    pixels
+}
+
+/// Short text for a value, with three significant digits.
+fn label(value: f32) -> String {
+    let magnitude = value.abs();
+    if magnitude == 0.0 {
+        return "0".to_string();
+    }
+    if !(0.01..1000.0).contains(&magnitude) {
+        return format!("{:.1e}", value);
+    }
+    let decimals = (2 - magnitude.log10().floor() as i32).max(0) as usize;
+    let text = format!("{:.*}", decimals, value);
+    if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        text
+    }
+}
+
+/// Draw text with its top left corner at (x, y), each glyph pixel a square of scale pixels.
+/// Characters without a glyph leave a blank.
+fn draw_text(pixels: &mut Grid<Color>, text: &str, x: usize, y: usize, scale: usize, color: Color) {
+    for (i, c) in text.chars().enumerate() {
+        let rows = match GLYPHS.iter().find(|&&(glyph, _)| glyph == c) {
+            Some(&(_, rows)) => rows,
+            None => continue,
+        };
+        for (row, bits) in rows.iter().enumerate() {
+            for column in 0..3 {
+                if bits & (4 >> column) == 0 {
+                    continue;
+                }
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        let px = x + (4 * i + column) * scale + dx;
+                        let py = y + row * scale + dy;
+                        let coord = Coord::new(px as i32, py as i32);
+                        if pixels.is_valid_coord(&coord) {
+                            pixels.set(coord, color);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn wheel_matches_the_field_colors() {
+    let scaling = Scaling::default();
+    let rgba = wheel(Colormap::PhaseDark, &scaling, 20);
+    assert_eq!(rgba.len(), 20 * 20 * 4);
+    assert_eq!(rgba[3], 0);
+    let pixel = |x: usize, y: usize| {
+        let i = 4 * (x + y * 20);
+        Color::new(rgba[i], rgba[i + 1], rgba[i + 2])
+    };
+    // the same value rendered in a field
+    let expected = |dx: f32, dy: f32| {
+        Complex::from_polar((dx * dx + dy * dy).sqrt() * scaling.max, dy.atan2(dx)).rgb()
+    };
+    assert_eq!(pixel(19, 10), expected(0.95, -0.05));
+    assert_eq!(pixel(10, 0), expected(0.05, 0.95));
+    assert_eq!(pixel(10, 10), expected(0.05, -0.05));
+    assert_ne!(pixel(19, 10), pixel(0, 10));
+}
+
+#[test]
+fn labels_are_short() {
+    assert_eq!(label(0.0), "0");
+    assert_eq!(label(0.25), "0.25");
+    assert_eq!(label(-12.5), "-12.5");
+    assert_eq!(label(2.0), "2");
+    assert_eq!(label(123.456), "123");
+    assert_eq!(label(0.00012), "1.2e-4");
+}
+
+#[test]
+fn colorbar_with_labels() {
+    let mut gradient = Grid::<Color>::new(2, 1);
+    gradient.data[0] = Color::new(0, 0, 255);
+    gradient.data[1] = Color::new(255, 0, 0);
+    let bar = colorbar(&gradient, -1.0, 1.0, 40, 24);
+    assert_eq!((bar.width, bar.height), (40, 24));
+    assert_eq!(bar.data[0], Color::new(0, 0, 255));
+    assert_eq!(bar.data[39], Color::new(255, 0, 0));
+    let ink = Color::new(0, 0, 0);
+    let labels = &bar.data[40 * 10..];
+    assert!(labels.iter().filter(|&&c| c == ink).count() > 20);
+    assert_eq!(bar.data[40 * 23], Color::white());
+    assert!(colorbar(&gradient, -1.0, 1.0, 0, 24).data.is_empty());
+    assert!(colorbar(&gradient, -1.0, 1.0, 40, 0).data.is_empty());
+}
@@ -7,21 +7,70 @@ extern crate rand;
 extern crate wasm_bindgen;
 extern crate web_sys;
 
+mod bohm;
 mod color;
+mod colormap;
 mod complex;
+mod contour;
 mod coord;
+mod fourier;
+mod gif;
 mod grid;
+mod legend;
+mod mask;
+mod observables;
+mod packet;
 mod pixel;
+mod png;
+mod potential;
+mod recorder;
+mod render;
+mod resample;
+mod scaling;
+mod scattering;
+mod spectrum;
+mod svg;
+mod terminal;
 mod utils;
+mod vortex;
+mod wigner;
 
-use complex::Complex;
-use coord::Coord;
+use bohm::Tracers;
+use color::Color;
+pub use colormap::Colormap;
+pub use complex::Complex;
+use contour::Polyline;
+pub use coord::Coord;
 use crossbeam_queue::SegQueue;
 use grid::Grid;
+pub use mask::Mask;
+pub use observables::Observables;
 use pixel::Pixel;
+use rand::Rng;
+use recorder::Recorder;
+pub use recorder::VideoFormat;
+use render::{Composite, Layer};
+pub use render::{Image, PotentialStyle, SinkStyle};
+pub use resample::Interpolation;
+use scaling::Scaling;
+pub use scattering::analytic_transmission;
+use scattering::Scattering;
+use spectrum::Autocorrelation;
 use std::f32::consts::PI;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use svg::Svg;
+pub use terminal::Palette;
+use vortex::VortexTracker;
 use wasm_bindgen::prelude::*;
 
+/// Cells with a smaller amplitude have a meaningless phase and are ignored when finding vortices.
+const VORTEX_THRESHOLD: f32 = 1e-6;
+
+/// Soft wall edges vanish beyond this many smoothing widths.
+const SOFT_WALL_REACH: f32 = 4.0;
+
 #[wasm_bindgen]
 pub struct Universe {
     width: usize,
@@ -32,7 +81,25 @@ pub struct Universe {
     sink_mult: Grid<f32>,
     potential_level: Grid<f32>,
     potential_cache: Grid<f32>,
+    barrier: Grid<f32>,
+    barrier_scale: f32,
+    auto_normalize: bool,
+    vortex_tracker: Option<VortexTracker>,
+    autocorrelation: Option<Autocorrelation>,
+    scattering: Option<Scattering>,
+    tracers: Tracers,
+    recorder: Option<Recorder>,
+    colormap: Colormap,
+    scaling: Scaling,
+    overlays: Composite,
+    cell_size: usize,
+    frame: Vec<u8>,
+    frame_width: usize,
+    frame_height: usize,
+    frame_generation: u32,
     max_tilt: f32,
+    x_slope: f32,
+    y_slope: f32,
     dt: f32,
 }
 
@@ -42,6 +109,7 @@ impl Universe {
     pub fn new(width: usize, height: usize) -> Universe {
         utils::set_panic_hook();
         let max_tilt = 0.0;
+        let (x_slope, y_slope) = (0.0, 0.0);
         let dt = 0.1;
 
         // Create a new grid of the given size
@@ -51,6 +119,21 @@ impl Universe {
         let sink_mult = Grid::<f32>::new(width, height);
         let potential_level = Grid::<f32>::new(width, height);
         let potential_cache = Grid::<f32>::new(width, height);
+        let barrier = Grid::<f32>::new(width, height);
+        let barrier_scale = 1.0;
+        let auto_normalize = false;
+        let vortex_tracker = None;
+        let autocorrelation = None;
+        let scattering = None;
+        let tracers = Tracers::default();
+        let recorder = None;
+        let colormap = Colormap::default();
+        let scaling = Scaling::default();
+        let overlays = Composite::default();
+        let cell_size = 1;
+        let frame = vec![255; width * height * 4];
+        let (frame_width, frame_height) = (width, height);
+        let frame_generation = 0;
 
         Universe {
             width,
@@ -61,7 +144,25 @@ impl Universe {
             sink_mult,
             potential_level,
             potential_cache,
+            barrier,
+            barrier_scale,
+            auto_normalize,
+            vortex_tracker,
+            autocorrelation,
+            scattering,
+            tracers,
+            recorder,
+            colormap,
+            scaling,
+            overlays,
+            cell_size,
+            frame,
+            frame_width,
+            frame_height,
+            frame_generation,
             max_tilt,
+            x_slope,
+            y_slope,
             dt,
         }
     }
@@ -77,60 +178,573 @@ impl Universe {
         self.ensure_no_positive_potential();
     }
 
+    /// Set the time step, in simulation time units.
+    /// The leapfrog is stable while dt times the largest energy on the grid stays below 2.
+    /// A running autocorrelation restarts from the current field, its samples being dt apart.
+    pub fn set_dt(&mut self, dt: f32) {
+        self.dt = dt;
+        if self.autocorrelation.is_some() {
+            self.set_autocorrelation(true);
+        }
+    }
+
+    /// Tilt the whole potential, slopes in [-1, 1] being fractions of the maximum tilt.
+    /// A positive x slope pulls the field to the right, a positive y slope downward.
+    pub fn set_tilt(&mut self, x_slope: f32, y_slope: f32) {
+        self.x_slope = x_slope.clamp(-1.0, 1.0);
+        self.y_slope = y_slope.clamp(-1.0, 1.0);
+    }
+
     /// Universe reset.
     pub fn reset(&mut self) {
         self.quantum.reset();
+        if let Some(tracker) = self.vortex_tracker.as_mut() {
+            tracker.clear();
+        }
     }
 
     /// Compute the steps throught the quantum field theory.
     pub fn step(&mut self) {
         let dt = self.dt;
-        let x_slope = 0.0;
-        let y_slope = 0.0;
+        let norm_before = if self.auto_normalize {
+            self.quantum.norm()
+        } else {
+            0.0
+        };
+        self.reset_potential_cache(self.x_slope, self.y_slope);
 
-        self.reset_potential_cache(x_slope, y_slope);
+        // Visscher leapfrog in kick, drift, kick form: the imaginary part moves half a step,
+        // the real part a whole step from it and the imaginary part the other half,
+        // so both parts of the stored field are at the same time between steps.
+        self.advance_imaginary(0.5 * dt, false);
+        self.advance_real(dt);
+        self.advance_imaginary(0.5 * dt, true);
 
-        for y in 1..self.height - 1 {
-            for x in 1..self.width - 1 {
-                let coord = Coord::new(x as i32, y as i32);
+        if self.auto_normalize {
+            self.quantum.normalize_to(norm_before);
+        }
+        if let Some(tracker) = self.vortex_tracker.as_mut() {
+            tracker.update(&vortex::find_vortices(&self.quantum, VORTEX_THRESHOLD));
+        }
+        if let Some(autocorrelation) = self.autocorrelation.as_mut() {
+            autocorrelation.record(&self.quantum);
+        }
+        if let Some(scattering) = self.scattering.as_mut() {
+            scattering.record(&self.quantum);
+        }
+        if !self.tracers.positions.is_empty() {
+            let current = self.quantum.current(&self.walls);
+            let (walls, sinks) = (&self.walls, &self.sinks);
+            self.tracers.advect(&self.quantum, &current, dt, |x, y| {
+                let coord = Coord::new(x, y);
+                *walls.get(coord).unwrap() || *sinks.get(coord).unwrap()
+            });
+        }
+        if self
+            .recorder
+            .as_mut()
+            .is_some_and(|recorder| recorder.tick())
+        {
+            self.render();
+            self.capture();
+        }
+    }
 
-                if !self.is_wall(coord) {
-                    let sink_mult = self.sink_mult.get(coord).unwrap();
-                    let potential_cache = self.potential_cache.get(coord).unwrap();
+    /// Measure probability, position, momentum, energy and their spreads.
+    pub fn observables(&self) -> Observables {
+        let potential = self.tilted_potential(self.x_slope, self.y_slope);
+        Observables::measure(&self.quantum, &potential)
+    }
 
-                    let cx = self.quantum.get(coord).unwrap();
-                    let top = self.quantum.get(coord.top()).unwrap();
-                    let bottom = self.quantum.get(coord.bottom()).unwrap();
-                    let left = self.quantum.get(coord.left()).unwrap();
-                    let right = self.quantum.get(coord.right()).unwrap();
+    /// Total probability of the quantum field.
+    pub fn norm(&self) -> f32 {
+        self.quantum.norm()
+    }
 
-                    let re = sink_mult
-                        * (cx.im
-                            + dt * (-0.5
-                                * (top.re + bottom.re + left.re + right.re - 4.0 * cx.re)
-                                + potential_cache * cx.re));
-                    let im = sink_mult
-                        * (cx.re
-                            + dt * (-0.5
-                                * (top.im + bottom.im + left.im + right.im - 4.0 * cx.im)
-                                + potential_cache * cx.im));
+    /// Scale the quantum field to a total probability of one.
+    pub fn normalize(&mut self) {
+        self.quantum.normalize_to(1.0);
+    }
 
-                    self.quantum.set(coord, Complex::new(re, im));
-                }
-            }
+    /// Multiply the quantum field by a complex factor.
+    pub fn scale(&mut self, factor: Complex) {
+        self.quantum.scale(factor);
+    }
+
+    /// Rotate the phase of the whole quantum field by theta radians.
+    pub fn apply_global_phase(&mut self, theta: f32) {
+        self.quantum.scale(Complex::from_polar(1.0, theta));
+    }
+
+    /// Zero the quantum field inside the mask.
+    pub fn clear_region(&mut self, mask: &Mask) {
+        self.quantum.clear(mask.grid());
+    }
+
+    /// Restore the total probability lost to sinks after every step.
+    pub fn set_auto_normalize(&mut self, enabled: bool) {
+        self.auto_normalize = enabled;
+    }
+
+    /// Select the colormap used by the renderers.
+    pub fn set_colormap(&mut self, colormap: Colormap) {
+        self.colormap = colormap;
+    }
+
+    /// Brightness proportional to the amplitude, full at max.
+    pub fn set_linear_scaling(&mut self, max: f32) {
+        self.scaling = Scaling::new(scaling::Mode::Linear, max);
+    }
+
+    /// Brightness proportional to log10 of the amplitude, from the given number of decades
+    /// below max, so that small tunnelled amplitudes stay visible.
+    pub fn set_log_scaling(&mut self, max: f32, decades: f32) {
+        self.scaling = Scaling::new(scaling::Mode::Log { decades }, max);
+    }
+
+    /// Brightness of the amplitude relative to max raised to the power gamma.
+    pub fn set_gamma_scaling(&mut self, max: f32, gamma: f32) {
+        self.scaling = Scaling::new(scaling::Mode::Gamma { gamma }, max);
+    }
+
+    /// Linear brightness, full at a running percentile in [0, 1] of the nonzero amplitudes.
+    /// Each render moves the exposure toward the current percentile by the smoothing fraction.
+    pub fn set_auto_exposure(&mut self, percentile: f32, smoothing: f32) {
+        let mode = scaling::Mode::Auto {
+            percentile,
+            smoothing,
+        };
+        self.scaling = Scaling::new(mode, self.scaling.max);
+    }
+
+    /// Amplitude currently rendered at full brightness.
+    pub fn exposure(&self) -> f32 {
+        self.scaling.max
+    }
+
+    /// Fill color and opacity of hard walls; finite walls are tinted by their relative height.
+    pub fn set_wall_layer(&mut self, color: Color, opacity: f32) {
+        self.overlays.walls = Layer::new(color, opacity);
+    }
+
+    /// Marking of sink cells, as a uniform tint or diagonal hatching.
+    pub fn set_sink_layer(&mut self, style: SinkStyle, color: Color, opacity: f32) {
+        self.overlays.sinks = Layer::new(color, opacity);
+        self.overlays.sink_style = style;
+    }
+
+    /// Display of the potential level, shaded by its relative magnitude
+    /// or as isolines every spacing potential units.
+    pub fn set_potential_layer(
+        &mut self,
+        style: PotentialStyle,
+        color: Color,
+        opacity: f32,
+        spacing: f32,
+    ) {
+        self.overlays.potential = Layer::new(color, opacity);
+        self.overlays.potential_style = style;
+        self.overlays.isoline_spacing = spacing;
+    }
+
+    /// Draw a line along the top and left edges of every cell, when cells span several pixels.
+    pub fn set_cell_grid(&mut self, enabled: bool, color: Color, opacity: f32) {
+        self.overlays.cell_grid = if enabled {
+            Some(Layer::new(color, opacity))
+        } else {
+            None
+        };
+    }
+
+    /// Number of pixels along the side of a cell in the rendered frame.
+    pub fn set_cell_size(&mut self, cell_size: usize) {
+        self.cell_size = cell_size.max(1);
+    }
+
+    /// Width in pixels of the last rendered frame.
+    pub fn frame_width(&self) -> usize {
+        self.frame_width
+    }
+
+    /// Height in pixels of the last rendered frame.
+    pub fn frame_height(&self) -> usize {
+        self.frame_height
+    }
+
+    /// Refresh the frame buffer from the quantum field, r, g, b, a pixels row after row,
+    /// with cell_size pixels per cell and the potential, sink, wall and grid overlays.
+    pub fn render(&mut self) {
+        self.scaling.expose(&self.quantum);
+        let colors = self.composite(self.cell_size);
+        self.present(&colors);
+    }
+
+    /// Refresh the frame buffer at width by height pixels, whatever the grid size,
+    /// interpolating the complex field before coloring it. Overlays follow the cells.
+    pub fn render_resampled(&mut self, width: usize, height: usize, interpolation: Interpolation) {
+        self.scaling.expose(&self.quantum);
+        let colors = self.composite_resampled(width, height, interpolation);
+        self.present(&colors);
+    }
+
+    /// Pointer to the frame buffer, valid until the generation changes.
+    pub fn frame_ptr(&self) -> *const u8 {
+        self.frame.as_ptr()
+    }
+
+    /// Length of the frame buffer in bytes.
+    pub fn frame_len(&self) -> usize {
+        self.frame.len()
+    }
+
+    /// Incremented whenever the frame buffer moves or is resized, so views on it must be rebuilt.
+    pub fn frame_generation(&self) -> u32 {
+        self.frame_generation
+    }
+
+    /// Record the rendered frame every interval steps, starting with the current state,
+    /// as an animated GIF or a YUV4MPEG2 stream played at fps frames per second.
+    /// Frames keep the size of the first one; renders of another size are skipped.
+    pub fn start_recording(&mut self, format: VideoFormat, interval: usize, fps: u32) {
+        self.recorder = Some(Recorder::new(format, interval, fps));
+        self.render();
+        self.capture();
+    }
+
+    /// Number of frames captured since the recording started.
+    pub fn recorded_frames(&self) -> usize {
+        self.recorder.as_ref().map_or(0, |recorder| recorder.frames)
+    }
+
+    /// Stop recording and return the file bytes, empty when not recording.
+    pub fn finish_recording(&mut self) -> Vec<u8> {
+        self.recorder.take().map_or(Vec::new(), Recorder::finish)
+    }
+
+    /// PNG file of the last rendered frame, or of a single field with the active colormap.
+    /// Fails on an empty frame, before the first render or after rendering at size zero.
+    pub fn png(&self, image: Image) -> Result<Vec<u8>, String> {
+        let mut bytes = Vec::new();
+        self.write_png(image, &mut bytes)
+            .map_err(|error| error.to_string())?;
+        Ok(bytes)
+    }
+
+    /// Phase wheel legend of size by size r, g, b, a pixels for the active colormap and scaling,
+    /// transparent outside the disk.
+    pub fn phase_wheel(&self, size: usize) -> Vec<u8> {
+        legend::wheel(self.colormap, &self.scaling, size)
+    }
+
+    /// PNG file of the phase wheel legend, failing for a zero size.
+    pub fn phase_wheel_png(&self, size: usize) -> Result<Vec<u8>, String> {
+        let mut bytes = Vec::new();
+        png::write(&mut bytes, size, size, &self.phase_wheel(size))
+            .map_err(|error| error.to_string())?;
+        Ok(bytes)
+    }
+
+    /// Labelled colorbar of width by height r, g, b, a pixels for a field with the active
+    /// colormap. The frame gets |ψ|² for density maps, the real or imaginary part for
+    /// those maps, and |ψ| at phase 0 for phase maps.
+    pub fn colorbar(&self, field: Image, width: usize, height: usize) -> Vec<u8> {
+        let (gradient, min, max) = self.gradient(field, width.max(1));
+        legend::colorbar(&gradient, min, max, width, height).rgba_bytes()
+    }
+
+    /// PNG file of the colorbar legend, failing for a zero width or height.
+    pub fn colorbar_png(
+        &self,
+        field: Image,
+        width: usize,
+        height: usize,
+    ) -> Result<Vec<u8>, String> {
+        let mut bytes = Vec::new();
+        png::write(
+            &mut bytes,
+            width,
+            height,
+            &self.colorbar(field, width, height),
+        )
+        .map_err(|error| error.to_string())?;
+        Ok(bytes)
+    }
+
+    /// SVG figure of the last rendered frame under the isolines of a field at the given levels
+    /// and the wall outlines, drawn with the potential and wall overlay colors.
+    /// The frame alone has no isolines. Coordinates are in cells, shown cell_size pixels wide.
+    /// An empty frame is left out.
+    pub fn svg(&self, field: Image, levels: Vec<f32>, cell_size: f32) -> String {
+        let mut svg = Svg::new(self.width, self.height, cell_size);
+        svg.image(self.frame_width, self.frame_height, &self.frame)
+            .ok();
+        let isolines: Vec<Polyline> = levels
+            .iter()
+            .flat_map(|&level| self.isolines(field, level))
+            .collect();
+        svg.polylines(&isolines, self.overlays.potential, 0.1);
+        svg.polylines(&contour::outlines(&self.walls), self.overlays.walls, 0.2);
+        svg.finish()
+    }
+
+    /// Render |ψ(k)|² colored by phase into the frame buffer, a pixel per cell with zero
+    /// momentum at the center. A Hann window can be applied first to reduce leakage from
+    /// the field edges. The scaling mode applies to |ψ(k)|², with the peak density at half
+    /// brightness.
+    pub fn render_momentum(&mut self, windowed: bool) {
+        let momentum = self.momentum_field(windowed);
+        let max = momentum
+            .data
+            .iter()
+            .fold(0.0, |max: f32, c| max.max(c.norm()));
+        let scaling = self.scaling.with_max(2.0 * max);
+        let colormap = self.colormap;
+        let colors = Grid {
+            width: self.width,
+            height: self.height,
+            data: momentum
+                .data
+                .iter()
+                .map(|c| {
+                    // density maps show the square of the radius they are given
+                    let level = scaling.level(c.norm());
+                    let radius = if colormap.is_density() {
+                        level.sqrt()
+                    } else {
+                        level
+                    };
+                    colormap.complex(Complex::from_polar(radius, c.arg()))
+                })
+                .collect(),
+        };
+        self.present(&colors);
+    }
+
+    /// Quantum field amplitudes as interleaved re, im values, row after row.
+    pub fn quantum_state(&self) -> Vec<f32> {
+        self.quantum
+            .data
+            .iter()
+            .flat_map(|c| vec![c.re, c.im])
+            .collect()
+    }
+
+    /// Centered momentum space amplitudes as interleaved re, im values, row after row.
+    pub fn momentum_state(&self, windowed: bool) -> Vec<f32> {
+        self.momentum_field(windowed)
+            .data
+            .iter()
+            .flat_map(|c| vec![c.re, c.im])
+            .collect()
+    }
+
+    /// Replace the quantum field by the inverse transform of centered momentum space
+    /// amplitudes, given as interleaved re, im values in the momentum_state layout.
+    pub fn load_momentum_state(&mut self, state: &[f32]) {
+        let mut momentum = Grid::<Complex>::new(self.width, self.height);
+        for (cell, pair) in momentum.data.iter_mut().zip(state.chunks(2)) {
+            *cell = Complex::new(pair[0], *pair.get(1).unwrap_or(&0.0));
         }
+        self.quantum = fourier::fft2(&fourier::shift(&momentum, true), true);
+        self.setup_walls();
     }
 
-    /// Retrieve cells for the web app.
-    pub fn quantum_ptr(&self) -> *const u8 {
-        let mut cells = Vec::new();
-        for cell in self.quantum.data.iter() {
-            let color = cell.rgb();
-            cells.push(color.r);
-            cells.push(color.g);
-            cells.push(color.b);
+    /// Wigner function of the field sampled along the cut from (x0, y0) toward (x1, y1),
+    /// one cell apart, as far as whole cells fit. Rows and columns are cuts along one axis.
+    /// Columns are positions, rows are momenta from +π/2 at the top down to -π/2.
+    pub fn wigner_function(&self, x0: f32, y0: f32, x1: f32, y1: f32) -> Vec<f32> {
+        self.wigner_cut(x0, y0, x1, y1).0.data
+    }
+
+    /// Render the Wigner function of a cut into the frame buffer with a diverging colormap,
+    /// negative values in blue. The scaling mode applies with the largest magnitude at full
+    /// saturation.
+    pub fn render_wigner(&mut self, x0: f32, y0: f32, x1: f32, y1: f32) {
+        let (w, _) = self.wigner_cut(x0, y0, x1, y1);
+        let max = w.data.iter().fold(0.0, |max: f32, v| max.max(v.abs()));
+        let scaling = self.scaling.with_max(max);
+        let mut colors = Grid::<Color>::new(w.width, w.height);
+        for (color, &value) in colors.data.iter_mut().zip(w.data.iter()) {
+            *color = colormap::diverging(scaling.signed(value));
         }
-        cells.as_ptr()
+        self.present(&colors);
+    }
+
+    /// Largest difference between the position marginal of the Wigner function of a cut
+    /// and |ψ|² along that cut.
+    pub fn wigner_marginal_error(&self, x0: f32, y0: f32, x1: f32, y1: f32) -> f32 {
+        let (w, psi) = self.wigner_cut(x0, y0, x1, y1);
+        wigner::marginal_error(&w, &psi)
+    }
+
+    /// Phase singularities of the field as x, y, winding triplets, positions in cell units.
+    pub fn vortices(&self) -> Vec<f32> {
+        vortex::find_vortices(&self.quantum, VORTEX_THRESHOLD)
+            .iter()
+            .flat_map(|v| vec![v.x, v.y, v.winding as f32])
+            .collect()
+    }
+
+    /// Link vortices into trajectories after every step, matching them within max_distance cells.
+    /// Disabling tracking drops the recorded trajectories.
+    pub fn set_vortex_tracking(&mut self, enabled: bool, max_distance: f32) {
+        self.vortex_tracker = if enabled {
+            Some(VortexTracker::new(max_distance))
+        } else {
+            None
+        };
+    }
+
+    /// Number of vortex trajectories recorded since tracking was enabled.
+    pub fn vortex_trajectory_count(&self) -> usize {
+        self.vortex_tracker
+            .as_ref()
+            .map_or(0, |tracker| tracker.trajectories.len())
+    }
+
+    /// Points of a vortex trajectory as interleaved x, y values.
+    pub fn vortex_trajectory(&self, index: usize) -> Vec<f32> {
+        self.vortex_tracker
+            .as_ref()
+            .and_then(|tracker| tracker.trajectories.get(index))
+            .map_or(Vec::new(), |trajectory| {
+                trajectory
+                    .points
+                    .iter()
+                    .flat_map(|&(x, y)| vec![x, y])
+                    .collect()
+            })
+    }
+
+    /// Render the field into the frame buffer with cell_size pixels per cell and ring every
+    /// vortex, white for positive and black for negative winding.
+    pub fn render_vortices(&mut self, cell_size: usize) {
+        let vortices = vortex::find_vortices(&self.quantum, VORTEX_THRESHOLD);
+        let mut frame = self.composite(cell_size);
+        let radius = 0.4 * cell_size as f32 + 1.0;
+        for &(winding, color) in [(1, Color::white()), (-1, Color::new(0, 0, 0))].iter() {
+            let points: Vec<(f32, f32)> = vortices
+                .iter()
+                .filter(|v| v.winding.signum() == winding)
+                .map(|v| (v.x + 0.5, v.y + 0.5))
+                .collect();
+            render::mark_points(&mut frame, &points, cell_size, radius, color);
+        }
+        self.present(&frame);
+    }
+
+    /// Record the autocorrelation with the current state after every step, or stop recording.
+    pub fn set_autocorrelation(&mut self, enabled: bool) {
+        self.autocorrelation = if enabled {
+            Some(Autocorrelation::new(&self.quantum, self.dt))
+        } else {
+            None
+        };
+    }
+
+    /// Recorded autocorrelation as interleaved re, im values, one pair per step.
+    pub fn autocorrelation(&self) -> Vec<f32> {
+        self.autocorrelation.as_ref().map_or(Vec::new(), |a| {
+            a.samples.iter().flat_map(|c| vec![c.re, c.im]).collect()
+        })
+    }
+
+    /// Energy spectrum of the recorded autocorrelation at bins energies from e_min to e_max.
+    pub fn energy_spectrum(&self, e_min: f32, e_max: f32, bins: usize) -> Vec<f32> {
+        self.autocorrelation
+            .as_ref()
+            .map_or(Vec::new(), |a| a.spectrum(e_min, e_max, bins))
+    }
+
+    /// Eigenvalues picked from the peaks of the energy spectrum.
+    /// The resolution is limited to about 2π over the recorded time.
+    pub fn energy_eigenvalues(&self, e_min: f32, e_max: f32, bins: usize) -> Vec<f32> {
+        spectrum::find_peaks(&self.energy_spectrum(e_min, e_max, bins), e_min, e_max)
+    }
+
+    /// Split the field into incident, barrier and transmitted zones along x, or y when vertical,
+    /// and integrate the probability in each zone after every step.
+    /// The packet is expected to come from the low coordinates.
+    pub fn set_scattering_zones(&mut self, barrier_start: f32, barrier_end: f32, vertical: bool) {
+        let mut scattering = Scattering::new(barrier_start, barrier_end, vertical);
+        scattering.record(&self.quantum);
+        self.scattering = Some(scattering);
+    }
+
+    /// Stop measuring zone probabilities.
+    pub fn clear_scattering_zones(&mut self) {
+        self.scattering = None;
+    }
+
+    /// Latest incident, barrier and transmitted probabilities.
+    pub fn zone_probabilities(&self) -> Vec<f32> {
+        self.scattering
+            .as_ref()
+            .and_then(|s| s.history.last())
+            .map_or(Vec::new(), |z| vec![z.incident, z.barrier, z.transmitted])
+    }
+
+    /// Fraction of the probability past the barrier.
+    pub fn transmission_coefficient(&self) -> f32 {
+        self.scattering
+            .as_ref()
+            .and_then(|s| s.history.last())
+            .map_or(0.0, |z| z.transmission())
+    }
+
+    /// Fraction of the probability back before the barrier.
+    pub fn reflection_coefficient(&self) -> f32 {
+        self.scattering
+            .as_ref()
+            .and_then(|s| s.history.last())
+            .map_or(0.0, |z| z.reflection())
+    }
+
+    /// True once the barrier is empty and the coefficients stopped moving over window steps,
+    /// both within tolerance.
+    pub fn scattering_settled(&self, window: usize, tolerance: f32) -> bool {
+        self.scattering
+            .as_ref()
+            .is_some_and(|s| s.is_settled(window, tolerance))
+    }
+
+    /// Replace the tracer particles by n new ones sampled from |ψ|².
+    /// They then follow Bohmian trajectories at every step, stopping at walls and sinks.
+    pub fn seed_tracers(&mut self, n: usize) {
+        self.seed_tracers_with(n, &mut rand::thread_rng());
+    }
+
+    /// Remove all tracer particles.
+    pub fn clear_tracers(&mut self) {
+        self.tracers = Tracers::default();
+    }
+
+    /// Tracer positions as interleaved x, y values in cell units, cell centers on integers.
+    pub fn tracer_positions(&self) -> Vec<f32> {
+        self.tracers
+            .positions
+            .iter()
+            .flat_map(|&(x, y)| vec![x, y])
+            .collect()
+    }
+
+    /// Probability current density as interleaved jx, jy values, row after row.
+    pub fn probability_current(&self) -> Vec<f32> {
+        let current = self.quantum.current(&self.walls);
+        current
+            .data
+            .iter()
+            .flat_map(|&(jx, jy)| vec![jx, jy])
+            .collect()
+    }
+
+    /// Render the field into the frame buffer with cell_size pixels per cell and overlay
+    /// the probability current as arrows every spacing cells.
+    pub fn render_current(&mut self, cell_size: usize, spacing: usize) {
+        let current = self.quantum.current(&self.walls);
+        let mut frame = self.composite(cell_size);
+        render::draw_arrows(&mut frame, &current, cell_size, spacing, Color::white());
+        self.present(&frame);
     }
 
     pub fn potential_level_ptr(&self) -> *const f32 {
@@ -141,6 +755,10 @@ impl Universe {
         self.potential_cache.data.as_ptr()
     }
 
+    pub fn barrier_ptr(&self) -> *const f32 {
+        self.barrier.data.as_ptr()
+    }
+
     /// Check if there is a wall at coord
     pub fn is_wall(&self, coord: Coord) -> bool {
         *self.walls.get(coord).unwrap()
@@ -206,90 +824,112 @@ impl Universe {
         }
     }
 
-    /// Add a gaussian distribution to the quantum complex field
+    /// Add a Gaussian packet centered on origin, the same packet as `add_wavepacket` with fx
+    /// and fy wave cycles across the width and height of the field. Its peak stays
+    /// a_scale (2π sigma²)^-1/4 as before, so its norm is a_scale² (2π sigma²)^1/2.
     pub fn add_gaussian(&mut self, origin: Coord, sigma: f32, fx: f32, fy: f32, a_scale: f32) {
-        let a: f32 = a_scale * (2.0 * PI * sigma * sigma).powf(-0.25);
-        let d: f32 = 4.0 * sigma * sigma;
-        let omega_x = 2.0 * PI * fx;
-        let omega_y = 2.0 * PI * fy;
-        let fwidth = self.width as f32;
-        let fheight = self.height as f32;
-
-        for x in 1..self.width - 1 {
-            for y in 1..self.height - 1 {
-                let coord = Coord::new(x as i32, y as i32);
-                let fx = x as f32;
-                let fy = y as f32;
-
-                let r2: f32 =
-                    ((fx - origin.x as f32).powf(2.) + (fy - origin.y as f32).powf(2.)) as f32;
-                let re = a
-                    * f32::exp(-r2 / d)
-                    * (omega_x * fx / fwidth).cos()
-                    * (omega_y * fy / fheight).cos();
-                let im = a
-                    * f32::exp(-r2 / d)
-                    * (omega_x * fx / fwidth).sin()
-                    * (omega_y * fy / fheight).sin();
-
-                let c = Complex::new(re, im);
-                self.quantum.add(coord, c);
+        let kx = 2.0 * PI * fx / self.width as f32;
+        let ky = 2.0 * PI * fy / self.height as f32;
+        let amplitude = Complex::new(a_scale * (2.0 * PI * sigma * sigma).powf(0.25), 0.0);
+        self.add_wavepacket(origin.x as f32, origin.y as f32, sigma, kx, ky, amplitude);
+    }
+
+    /// Add a Gaussian packet centered on (cx, cy) with position spread sigma and wavevector (kx, ky).
+    pub fn add_wavepacket(
+        &mut self,
+        cx: f32,
+        cy: f32,
+        sigma: f32,
+        kx: f32,
+        ky: f32,
+        amplitude: Complex,
+    ) {
+        self.add_packet_with(cx, cy, amplitude, |dx, dy| {
+            packet::gaussian(dx, dy, sigma, kx, ky)
+        });
+    }
+
+    /// Add a plane wave with wavevector (kx, ky) over the whole field.
+    pub fn add_plane_wave(&mut self, kx: f32, ky: f32, amplitude: Complex) {
+        self.add_packet_with(0.0, 0.0, amplitude, |x, y| packet::plane_wave(x, y, kx, ky));
+    }
+
+    /// Add a Hermite–Gauss mode of orders (m, n) centered on (cx, cy).
+    pub fn add_hermite_gauss(
+        &mut self,
+        cx: f32,
+        cy: f32,
+        waist: f32,
+        m: u32,
+        n: u32,
+        amplitude: Complex,
+    ) {
+        self.add_packet_with(cx, cy, amplitude, |dx, dy| {
+            packet::hermite_gauss(dx, dy, waist, m, n)
+        });
+    }
+
+    /// Add a Laguerre–Gauss vortex mode of radial order p and winding l centered on (cx, cy).
+    pub fn add_laguerre_gauss(
+        &mut self,
+        cx: f32,
+        cy: f32,
+        waist: f32,
+        p: u32,
+        l: i32,
+        amplitude: Complex,
+    ) {
+        self.add_packet_with(cx, cy, amplitude, |dx, dy| {
+            packet::laguerre_gauss(dx, dy, waist, p, l)
+        });
+    }
+
+    /// Add a coherent state of a harmonic trap of frequency omega at (cx, cy) with momentum (px, py).
+    pub fn add_coherent_state(
+        &mut self,
+        cx: f32,
+        cy: f32,
+        omega: f32,
+        px: f32,
+        py: f32,
+        amplitude: Complex,
+    ) {
+        self.add_packet_with(cx, cy, amplitude, |dx, dy| {
+            packet::coherent_state(dx, dy, omega, px, py)
+        });
+    }
+
+    /// Add potential plane to the potential level field.
+    /// The four corner potentials are bilinearly interpolated over the bounding box of the mask,
+    /// and only cells inside the mask are affected.
+    pub fn add_potential_plane(&mut self, tl: f32, tr: f32, bl: f32, br: f32, mask: &Mask) {
+        let (top_left, bottom_right) = match mask.grid().bounds() {
+            Some(bounds) => bounds,
+            None => return,
+        };
+        let pot_width = usize::max((bottom_right.x - top_left.x) as usize, 1) as f32;
+        let pot_height = usize::max((bottom_right.y - top_left.y) as usize, 1) as f32;
+
+        for y in top_left.y..=bottom_right.y {
+            for x in top_left.x..=bottom_right.x {
+                let coord = Coord::new(x, y);
+                if *mask.grid().get(coord).unwrap() && self.potential_level.is_valid_coord(&coord) {
+                    let rx = (x - top_left.x) as f32 / pot_width;
+                    let ry = (y - top_left.y) as f32 / pot_height;
+                    // potx0, potxh = potential at x,0 and x,height
+                    let potx0 = tl + (tr - tl) * rx;
+                    let potxh = bl + (br - bl) * rx;
+                    let p = potx0 + (potxh - potx0) * ry;
+                    self.potential_level.add(coord, p);
+                }
             }
         }
     }
 
-    /// Add potential plane to the potential level field
-    // pub fn addPotentialPlane(&self, tl: f32, tr: f32, bl: f32, br: f32, mask: Grid<bool>) {
-    //     //find extremes
-    //     let top = mask.height();
-    //     let left = mask.width();
-    //     let bottom = 0;
-    //     let right = 0;
-
-    //     for x in 0..mask.width() {
-    //         for y in 0..mask.height() {
-    //             if mask.get(x, y) {
-    //                 if x < left {
-    //                     left = x;
-    //                 }
-    //                 if x > right {
-    //                     right = x;
-    //                 }
-    //                 if y < top {
-    //                     top = y;
-    //                 }
-    //                 if y > bottom {
-    //                     bottom = y;
-    //                 }
-    //             }
-    //         }
-    //     }
-    //     //add potential
-    //     let pot_width = right - left;
-
-    //     if pot_width == 0 {
-    //         pot_width = 1;
-    //     }
-    //     let pot_height = bottom - top;
-    //     if pot_height == 0 {
-    //         pot_height = 1;
-    //     }
-    //     for x in left..right {
-    //         for y in top..bottom {
-    //             if mask.get(x, y) {
-    //                 let coord = Coord::new(x as i32, y as i32);
-
-    //                 let rx = (x - left) / pot_width as f32;
-    //                 let ry = (y - top) / pot_height as f32;
-    //                 // potx0, potxh = potential at x,0 and x,height
-    //                 let potx0 = tl + (tr - tl) * rx;
-    //                 let potxh = bl + (br - bl) * rx;
-    //                 let p = potx0 + (potxh - potx0) * ry;
-    //                 self.potential_level.add(x, y, p);
-    //             }
-    //         }
-    //     }
-    // }
+    /// Create an empty mask matching the universe size.
+    pub fn new_mask(&self) -> Mask {
+        Mask::new(self.width, self.height)
+    }
 
     /// Add potential cone starting from a given point
     pub fn add_potential_cone(&mut self, origin: Coord, radius: f32, depth: f32) {
@@ -327,6 +967,98 @@ impl Universe {
         }
     }
 
+    /// Add a finite-height wall over the rectangle spanned by two corners.
+    /// A non-zero smoothing softens the edges over that many cells with a tanh profile,
+    /// leaving cells further than four smoothing widths from the rectangle untouched.
+    pub fn add_soft_wall(&mut self, from: Coord, to: Coord, height: f32, smoothing: f32) {
+        let (x0, x1) = (from.x.min(to.x) as f32, from.x.max(to.x) as f32);
+        let (y0, y1) = (from.y.min(to.y) as f32, from.y.max(to.y) as f32);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let coord = Coord::new(x as i32, y as i32);
+                let profile = edge_profile(x as f32, x0, x1, smoothing)
+                    * edge_profile(y as f32, y0, y1, smoothing);
+                if profile > 0.0 {
+                    self.barrier.add(coord, height * profile);
+                }
+            }
+        }
+    }
+
+    /// Scale every finite wall at once, e.g. to lower barriers from the UI.
+    pub fn set_barrier_scale(&mut self, scale: f32) {
+        self.barrier_scale = scale;
+    }
+
+    /// Remove all finite walls.
+    pub fn clear_barriers(&mut self) {
+        self.barrier = Grid::<f32>::new(self.width, self.height);
+    }
+
+    /// Add a 2D harmonic oscillator centered on origin, with its u axis rotated by angle.
+    pub fn add_harmonic_potential(
+        &mut self,
+        origin: Coord,
+        angle: f32,
+        omega_u: f32,
+        omega_v: f32,
+    ) {
+        self.add_potential_with(origin, angle, |u, v| {
+            potential::harmonic(u, v, omega_u, omega_v)
+        });
+    }
+
+    /// Add a finite step rising past the line through origin, facing angle.
+    pub fn add_potential_step(&mut self, origin: Coord, angle: f32, height: f32) {
+        self.add_potential_with(origin, angle, |u, _| potential::step(u, height));
+    }
+
+    /// Add a rectangular barrier of the given width through origin, crossed along angle.
+    pub fn add_potential_barrier(&mut self, origin: Coord, angle: f32, width: f32, height: f32) {
+        self.add_potential_with(origin, angle, |u, _| potential::barrier(u, width, height));
+    }
+
+    /// Add a double well centered on origin, its minima separated along angle.
+    pub fn add_double_well(
+        &mut self,
+        origin: Coord,
+        angle: f32,
+        separation: f32,
+        barrier_height: f32,
+    ) {
+        self.add_potential_with(origin, angle, |u, _| {
+            potential::double_well(u, separation, barrier_height)
+        });
+    }
+
+    /// Add a softened Coulomb potential centered on origin; being radial it has no orientation.
+    pub fn add_soft_coulomb(&mut self, origin: Coord, charge: f32, softening: f32) {
+        self.add_potential_with(origin, 0.0, |u, v| {
+            potential::soft_coulomb(u, v, charge, softening)
+        });
+    }
+
+    /// Add a Kronig–Penney lattice of barriers starting at origin and repeating along angle.
+    pub fn add_kronig_penney(
+        &mut self,
+        origin: Coord,
+        angle: f32,
+        period: f32,
+        width: f32,
+        height: f32,
+    ) {
+        self.add_potential_with(origin, angle, |u, _| {
+            potential::kronig_penney(u, period, width, height)
+        });
+    }
+
+    /// Add an egg-crate lattice with a minimum at origin, its axes rotated by angle.
+    pub fn add_egg_crate(&mut self, origin: Coord, angle: f32, period: f32, depth: f32) {
+        self.add_potential_with(origin, angle, |u, v| {
+            potential::egg_crate(u, v, period, depth)
+        });
+    }
+
     /// Toggle cell at coord according to active field
     pub fn toggle_cell(&mut self, x: i32, y: i32, active_field: String) {
         let origin = Coord::new(x, y);
@@ -342,6 +1074,12 @@ impl Universe {
     /// pixel wide band with potential +1 above background - tunnelling
     /// potential of -5 over width of universe - good for steering
     fn reset_potential_cache(&mut self, x_slope: f32, y_slope: f32) {
+        self.potential_cache = self.tilted_potential(x_slope, y_slope);
+    }
+
+    /// Potential level and finite walls under a tilt, as used by the step.
+    fn tilted_potential(&self, x_slope: f32, y_slope: f32) -> Grid<f32> {
+        let mut potential = Grid::<f32>::new(self.width, self.height);
         //if tilting 2 directions at once reduce tilt to compensate
         let total_slope = x_slope.abs() + y_slope.abs();
         let tilt = if total_slope <= 1.0 {
@@ -352,8 +1090,8 @@ impl Universe {
 
         //compute desired relative potentials of corners
         let largest_dim = usize::max(self.width, self.height);
-        let right_change = -x_slope * tilt * (self.width / largest_dim) as f32;
-        let down_change = -y_slope * tilt * (self.height / largest_dim) as f32;
+        let right_change = -x_slope * tilt * self.width as f32 / largest_dim as f32;
+        let down_change = -y_slope * tilt * self.height as f32 / largest_dim as f32;
         let top_left = -right_change - down_change;
         let top_right = right_change - down_change;
         let down_left = -right_change + down_change;
@@ -376,12 +1114,15 @@ impl Universe {
             for x in 0..self.width {
                 let coord = Coord::new(x as i32, y as i32);
                 current_pot += x_pot_step;
-                self.potential_cache.set(
+                potential.set(
                     coord,
-                    current_pot + self.potential_level.get(coord).unwrap(),
+                    current_pot
+                        + self.potential_level.get(coord).unwrap()
+                        + self.barrier_scale * self.barrier.get(coord).unwrap(),
                 );
             }
         }
+        potential
     }
 
     /// Ensure there is no positive potential
@@ -398,6 +1139,329 @@ impl Universe {
     }
 }
 
+/// Native methods for Rust callers, not exported to JavaScript.
+impl Universe {
+    /// Replace the tracer particles by n new ones drawn from |ψ|² with the given generator.
+    pub fn seed_tracers_with<R: Rng>(&mut self, n: usize, rng: &mut R) {
+        self.tracers = Tracers::sample(&self.quantum, n, rng);
+    }
+
+    /// Write a PNG file of the last rendered frame, or of a single field with the active colormap.
+    pub fn write_png<W: Write>(&self, image: Image, writer: W) -> io::Result<()> {
+        match self.scalar_field(image) {
+            None => png::write(writer, self.frame_width, self.frame_height, &self.frame),
+            Some(field) => {
+                let colors = field.colors(self.colormap, &self.scaling);
+                png::write(writer, colors.width, colors.height, &colors.rgba_bytes())
+            }
+        }
+    }
+
+    /// Save a PNG file of the last rendered frame or of a single field at the given path.
+    pub fn save_png<P: AsRef<Path>>(&self, image: Image, path: P) -> io::Result<()> {
+        let mut writer = io::BufWriter::new(File::create(path)?);
+        self.write_png(image, &mut writer)?;
+        writer.flush()
+    }
+
+    /// Lines where a field crosses level, in cell units; none for the frame.
+    pub fn isolines(&self, field: Image, level: f32) -> Vec<Polyline> {
+        self.scalar_field(field)
+            .map_or(Vec::new(), |field| contour::isolines(&field, level))
+    }
+
+    /// Save an SVG figure of the last rendered frame with isolines and wall outlines.
+    pub fn save_svg<P: AsRef<Path>>(
+        &self,
+        field: Image,
+        levels: Vec<f32>,
+        cell_size: f32,
+        path: P,
+    ) -> io::Result<()> {
+        File::create(path)?.write_all(self.svg(field, levels, cell_size).as_bytes())
+    }
+
+    /// Composited frame with a pixel per cell as terminal text, two cells per character.
+    pub fn terminal_frame(&self, palette: Palette) -> String {
+        terminal::half_blocks(&self.composite(1), palette)
+    }
+}
+
+/// Internal methods, not exported to JavaScript.
+impl Universe {
+    /// Move the real part of the field dt ahead from the imaginary part, damped by the sinks.
+    fn advance_real(&mut self, dt: f32) {
+        for y in 1..self.height - 1 {
+            for x in 1..self.width - 1 {
+                let coord = Coord::new(x as i32, y as i32);
+
+                if !self.is_wall(coord) {
+                    let sink_mult = self.sink_mult.get(coord).unwrap();
+                    let potential_cache = self.potential_cache.get(coord).unwrap();
+
+                    let cx = self.quantum.get(coord).unwrap();
+                    let top = self.quantum.get(coord.top()).unwrap();
+                    let bottom = self.quantum.get(coord.bottom()).unwrap();
+                    let left = self.quantum.get(coord.left()).unwrap();
+                    let right = self.quantum.get(coord.right()).unwrap();
+
+                    let re = sink_mult
+                        * (cx.re
+                            + dt * (-0.5
+                                * (top.im + bottom.im + left.im + right.im - 4.0 * cx.im)
+                                + potential_cache * cx.im));
+
+                    self.quantum.set(coord, Complex::new(re, cx.im));
+                }
+            }
+        }
+    }
+
+    /// Move the imaginary part of the field dt ahead from the real part,
+    /// damped by the sinks when asked.
+    fn advance_imaginary(&mut self, dt: f32, damped: bool) {
+        for y in 1..self.height - 1 {
+            for x in 1..self.width - 1 {
+                let coord = Coord::new(x as i32, y as i32);
+
+                if !self.is_wall(coord) {
+                    let sink_mult = if damped {
+                        *self.sink_mult.get(coord).unwrap()
+                    } else {
+                        1.0
+                    };
+                    let potential_cache = self.potential_cache.get(coord).unwrap();
+
+                    let cx = self.quantum.get(coord).unwrap();
+                    let top = self.quantum.get(coord.top()).unwrap();
+                    let bottom = self.quantum.get(coord.bottom()).unwrap();
+                    let left = self.quantum.get(coord.left()).unwrap();
+                    let right = self.quantum.get(coord.right()).unwrap();
+
+                    let im = sink_mult
+                        * (cx.im
+                            - dt * (-0.5
+                                * (top.re + bottom.re + left.re + right.re - 4.0 * cx.re)
+                                + potential_cache * cx.re));
+
+                    self.quantum.set(coord, Complex::new(cx.re, im));
+                }
+            }
+        }
+    }
+
+    /// Wigner function of a cut along with the sampled field.
+    fn wigner_cut(&self, x0: f32, y0: f32, x1: f32, y1: f32) -> (Grid<f32>, Vec<Complex>) {
+        // unit spacing keeps the momenta of the rows in radians per cell
+        let length = (x1 - x0).hypot(y1 - y0);
+        let cells = (length + 1e-3).floor();
+        let end = if cells > 0.0 {
+            (
+                x0 + (x1 - x0) * cells / length,
+                y0 + (y1 - y0) * cells / length,
+            )
+        } else {
+            (x0, y0)
+        };
+        let psi = wigner::sample_line(&self.quantum, (x0, y0), end, cells as usize + 1);
+        (wigner::wigner(&psi), psi)
+    }
+
+    /// Unitary Fourier transform of the quantum field with zero momentum at the center.
+    fn momentum_field(&self, windowed: bool) -> Grid<Complex> {
+        let field = if windowed {
+            fourier::hann_window(&self.quantum)
+        } else {
+            self.quantum.clone()
+        };
+        fourier::shift(&fourier::fft2(&field, false), false)
+    }
+
+    /// Row of width colors of a field from its lowest to its highest shown value,
+    /// colored by the same code as the field, along with those two values.
+    fn gradient(&self, field: Image, width: usize) -> (Grid<Color>, f32, f32) {
+        let ramp = |min: f32, max: f32| {
+            let step = (max - min) / (width.max(2) - 1) as f32;
+            (0..width).map(move |x| min + step * x as f32)
+        };
+        match self.scalar_field(field) {
+            None => {
+                let max = self.scaling.max;
+                let (min, max) = match self.colormap {
+                    c if c.is_density() => (0.0, max * max),
+                    Colormap::RealPart | Colormap::ImaginaryPart => (-max, max),
+                    _ => (0.0, max),
+                };
+                let values = Grid {
+                    width,
+                    height: 1,
+                    data: ramp(min, max)
+                        .map(|v| match self.colormap {
+                            c if c.is_density() => Complex::new(v.sqrt(), 0.0),
+                            Colormap::ImaginaryPart => Complex::new(0.0, v),
+                            _ => Complex::new(v, 0.0),
+                        })
+                        .collect(),
+                };
+                (values.colors(self.colormap, &self.scaling), min, max)
+            }
+            Some(field) => {
+                let low = field.data.iter().cloned().fold(f32::INFINITY, f32::min);
+                let high = field.data.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+                let (min, max) = if self.colormap.is_density() {
+                    (low, high)
+                } else {
+                    let magnitude = low.abs().max(high.abs());
+                    (-magnitude, magnitude)
+                };
+                let values = Grid {
+                    width,
+                    height: 1,
+                    data: ramp(min, max).collect(),
+                };
+                (values.colors(self.colormap, &self.scaling), min, max)
+            }
+        }
+    }
+
+    /// Values of a field with one per cell, none for the rendered frame.
+    fn scalar_field(&self, image: Image) -> Option<Grid<f32>> {
+        match image {
+            Image::Frame => None,
+            Image::Density => Some(Grid {
+                width: self.width,
+                height: self.height,
+                data: self.quantum.data.iter().map(|c| c.norm()).collect(),
+            }),
+            Image::PotentialLevel => Some(self.potential_level.clone()),
+            Image::SinkMult => Some(self.sink_mult.clone()),
+        }
+    }
+
+    /// Quantum field colored with cell_size pixels per cell, under the composite overlays.
+    fn composite(&self, cell_size: usize) -> Grid<Color> {
+        let mut frame = self
+            .quantum
+            .colors(self.colormap, &self.scaling)
+            .upscale(cell_size.max(1));
+        self.draw_overlays(&mut frame);
+        frame
+    }
+
+    /// Quantum field interpolated to width by height pixels before coloring,
+    /// under the composite overlays.
+    fn composite_resampled(
+        &self,
+        width: usize,
+        height: usize,
+        interpolation: Interpolation,
+    ) -> Grid<Color> {
+        let field = resample::resample(&self.quantum, width, height, interpolation);
+        let mut frame = field.colors(self.colormap, &self.scaling);
+        self.draw_overlays(&mut frame);
+        frame
+    }
+
+    fn draw_overlays(&self, frame: &mut Grid<Color>) {
+        let scene = render::Scene {
+            walls: &self.walls,
+            barrier: &self.barrier,
+            sinks: &self.sinks,
+            potential: &self.potential_level,
+        };
+        self.overlays.draw(frame, &scene);
+    }
+
+    /// Hand the frame buffer to the recorder.
+    fn capture(&mut self) {
+        if let Some(recorder) = self.recorder.as_mut() {
+            // frames too large for the format are left out of the recording
+            recorder
+                .capture(self.frame_width, self.frame_height, &self.frame)
+                .ok();
+        }
+    }
+
+    /// Copy colors into the frame buffer, bumping the generation when it moves or changes size.
+    fn present(&mut self, colors: &Grid<Color>) {
+        let before = (
+            self.frame.as_ptr(),
+            self.frame.len(),
+            self.frame_width,
+            self.frame_height,
+        );
+        colors.write_rgba(&mut self.frame);
+        self.frame_width = colors.width;
+        self.frame_height = colors.height;
+        let after = (
+            self.frame.as_ptr(),
+            self.frame.len(),
+            self.frame_width,
+            self.frame_height,
+        );
+        if after != before {
+            self.frame_generation = self.frame_generation.wrapping_add(1);
+        }
+    }
+
+    /// Add amplitude times the packet computed from each cell offset to (cx, cy).
+    /// Border cells and walls are left untouched.
+    fn add_packet_with<F: Fn(f32, f32) -> Complex>(
+        &mut self,
+        cx: f32,
+        cy: f32,
+        amplitude: Complex,
+        f: F,
+    ) {
+        for y in 1..self.height - 1 {
+            for x in 1..self.width - 1 {
+                let coord = Coord::new(x as i32, y as i32);
+                if !self.is_wall(coord) {
+                    let value = f(x as f32 - cx, y as f32 - cy);
+                    self.quantum.add(coord, amplitude.mul(&value));
+                }
+            }
+        }
+    }
+
+    /// Add to each cell the potential computed from its offset to origin, rotated by angle.
+    fn add_potential_with<F: Fn(f32, f32) -> f32>(&mut self, origin: Coord, angle: f32, f: F) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let coord = Coord::new(x as i32, y as i32);
+                let dx = (x as i32 - origin.x) as f32;
+                let dy = (y as i32 - origin.y) as f32;
+                let (u, v) = potential::rotate(dx, dy, angle);
+                self.potential_level.add(coord, f(u, v));
+            }
+        }
+    }
+}
+
+/// Fraction of a barrier spanning [lo, hi] present at position p.
+/// Without smoothing the barrier is a hard step including both ends.
+/// The tanh edges are cut off SOFT_WALL_REACH smoothing widths away from the barrier.
+fn edge_profile(p: f32, lo: f32, hi: f32, smoothing: f32) -> f32 {
+    if smoothing <= 0.0 {
+        if p >= lo && p <= hi {
+            1.0
+        } else {
+            0.0
+        }
+    } else if p < lo - 0.5 - SOFT_WALL_REACH * smoothing
+        || p > hi + 0.5 + SOFT_WALL_REACH * smoothing
+    {
+        0.0
+    } else {
+        0.5 * (((p - lo + 0.5) / smoothing).tanh() - ((p - hi - 0.5) / smoothing).tanh())
+    }
+}
+
+#[cfg(test)]
+use rand::rngs::StdRng;
+#[cfg(test)]
+use rand::SeedableRng;
+
 #[cfg(test)]
 #[test]
 fn create_universe() {
@@ -502,3 +1566,571 @@ fn steps() {
         println!("{:?}", u.quantum.data);
     }
 }
+
+#[test]
+fn adding_soft_wall() {
+    let mut u = Universe::new(10, 10);
+    u.add_soft_wall(Coord::new(4, 2), Coord::new(5, 7), 2.0, 0.0);
+    assert_eq!(*u.barrier.get(Coord::new(4, 4)).unwrap(), 2.0);
+    assert_eq!(*u.barrier.get(Coord::new(3, 4)).unwrap(), 0.0);
+
+    u.clear_barriers();
+    u.add_soft_wall(Coord::new(4, 2), Coord::new(5, 7), 2.0, 0.5);
+    let inside = *u.barrier.get(Coord::new(4, 4)).unwrap();
+    let edge = *u.barrier.get(Coord::new(3, 4)).unwrap();
+    let tail = *u.barrier.get(Coord::new(2, 4)).unwrap();
+    assert!(inside > edge && edge > tail && tail > 0.0);
+    assert_eq!(*u.barrier.get(Coord::new(1, 4)).unwrap(), 0.0);
+    assert_eq!(*u.barrier.get(Coord::new(9, 9)).unwrap(), 0.0);
+    println!("{}", u.barrier);
+}
+
+#[test]
+fn soft_wall_folded_into_potential_cache() {
+    let mut u = Universe::new(8, 8);
+    let coord = Coord::new(4, 4);
+    u.add_soft_wall(coord, coord, 1.5, 0.0);
+    u.reset_potential_cache(0.0, 0.0);
+    assert_eq!(*u.potential_cache.get(coord).unwrap(), 1.5);
+
+    u.set_barrier_scale(0.5);
+    u.reset_potential_cache(0.0, 0.0);
+    assert_eq!(*u.potential_cache.get(coord).unwrap(), 0.75);
+}
+
+#[test]
+fn adding_potential_plane() {
+    let mut u = Universe::new(8, 8);
+    let mut mask = u.new_mask();
+    mask.add_rect(2, 2, 6, 4);
+    u.add_potential_plane(0.0, 4.0, 2.0, 6.0, &mask);
+    println!("{}", u.potential_level);
+    assert_eq!(*u.potential_level.get(Coord::new(2, 2)).unwrap(), 0.0);
+    assert_eq!(*u.potential_level.get(Coord::new(6, 2)).unwrap(), 4.0);
+    assert_eq!(*u.potential_level.get(Coord::new(2, 4)).unwrap(), 2.0);
+    assert_eq!(*u.potential_level.get(Coord::new(6, 4)).unwrap(), 6.0);
+    assert_eq!(*u.potential_level.get(Coord::new(4, 3)).unwrap(), 3.0);
+    assert_eq!(*u.potential_level.get(Coord::new(1, 3)).unwrap(), 0.0);
+}
+
+#[test]
+fn adding_rotated_potential_barrier() {
+    let mut u = Universe::new(9, 9);
+    u.add_potential_barrier(Coord::new(4, 4), PI / 2.0, 1.0, 2.0);
+    println!("{}", u.potential_level);
+    assert_eq!(*u.potential_level.get(Coord::new(0, 4)).unwrap(), 2.0);
+    assert_eq!(*u.potential_level.get(Coord::new(8, 4)).unwrap(), 2.0);
+    assert_eq!(*u.potential_level.get(Coord::new(4, 2)).unwrap(), 0.0);
+}
+
+#[test]
+fn adding_wavepackets_in_superposition() {
+    let mut u = Universe::new(40, 40);
+    u.add_wavepacket(15.5, 20.0, 3.0, 0.5, 0.0, Complex::new(1.0, 0.0));
+    u.add_wavepacket(24.5, 20.0, 3.0, -0.5, 0.0, Complex::new(-1.0, 0.0));
+    let total: f32 = u.quantum.data.iter().map(|c| c.norm()).sum();
+    assert!((total - 2.0).abs() < 0.05);
+    assert_eq!(u.quantum.get(Coord::new(20, 20)).unwrap().re, 0.0);
+}
+
+#[test]
+fn gaussian_is_a_travelling_wavepacket() {
+    let (mut gaussian, mut packet) = (Universe::new(40, 20), Universe::new(40, 20));
+    gaussian.add_gaussian(Coord::new(20, 10), 3.0, 4.0, 0.0, 0.5);
+    let kx = 2.0 * PI * 4.0 / 40.0;
+    let amplitude = 0.5 * (2.0 * PI * 9.0f32).powf(0.25);
+    packet.add_wavepacket(20.0, 10.0, 3.0, kx, 0.0, Complex::new(amplitude, 0.0));
+    assert_eq!(gaussian.quantum, packet.quantum);
+    assert!((gaussian.norm() - amplitude * amplitude).abs() < 0.05);
+
+    // the peak keeps the original amplitude a_scale (2π sigma²)^-1/4
+    let mut u = Universe::new(20, 20);
+    u.add_gaussian(Coord::new(10, 10), 2.0, 0.0, 0.0, 1.0);
+    let peak = u.quantum.get(Coord::new(10, 10)).unwrap().radius();
+    assert!((peak - (8.0 * PI).powf(-0.25)).abs() < 1e-5);
+}
+
+#[test]
+fn global_operations_on_quantum() {
+    let mut u = Universe::new(20, 20);
+    u.add_wavepacket(9.5, 9.5, 2.0, 0.0, 0.0, Complex::new(3.0, 0.0));
+    assert!((u.norm() - 9.0).abs() < 0.05);
+    u.normalize();
+    assert!((u.norm() - 1.0).abs() < 1e-5);
+
+    let before = *u.quantum.get(Coord::new(9, 9)).unwrap();
+    u.apply_global_phase(PI / 2.0);
+    let after = *u.quantum.get(Coord::new(9, 9)).unwrap();
+    assert!((after.re - 0.0).abs() < 1e-6 && (after.im - before.re).abs() < 1e-6);
+    assert!((u.norm() - 1.0).abs() < 1e-5);
+
+    let mut mask = u.new_mask();
+    mask.add_rect(0, 0, 9, 19);
+    u.clear_region(&mask);
+    assert!((u.norm() - 0.5).abs() < 0.05);
+}
+
+#[test]
+fn auto_normalize_after_step() {
+    let mut u = Universe::new(20, 20);
+    u.setup();
+    u.add_wavepacket(10.0, 10.0, 2.0, 0.0, 0.0, Complex::new(1.0, 0.0));
+    u.set_auto_normalize(true);
+    let norm = u.norm();
+    u.step();
+    assert!((u.norm() - norm).abs() < 1e-4);
+}
+
+#[test]
+fn leapfrog_moves_a_packet_at_the_group_velocity() {
+    let mut u = Universe::new(60, 30);
+    u.setup();
+    u.add_wavepacket(20.0, 15.0, 3.0, 0.5, 0.0, Complex::new(1.0, 0.0));
+    let centroid = |u: &Universe| {
+        let weighted: f32 = (u.quantum.data.iter().enumerate())
+            .map(|(i, c)| (i % u.width) as f32 * c.norm())
+            .sum();
+        weighted / u.norm()
+    };
+    let (start, norm) = (centroid(&u), u.norm());
+    for _i in 0..100 {
+        u.step();
+    }
+    // the lattice dispersion 1 - cos k has group velocity sin k
+    let travelled = centroid(&u) - start;
+    assert!(
+        (travelled - 100.0 * 0.1 * 0.5f32.sin()).abs() < 0.3,
+        "{}",
+        travelled
+    );
+    assert!((u.norm() - norm).abs() < 1e-3);
+}
+
+#[test]
+fn leapfrog_is_time_reversible() {
+    let mut u = Universe::new(30, 30);
+    u.setup();
+    u.add_wavepacket(15.0, 15.0, 3.0, 0.5, -0.3, Complex::new(1.0, 0.0));
+    let initial = u.quantum.clone();
+    // the stored field has both parts at the same time, so conjugating it runs time backward
+    for _i in 0..50 {
+        u.step();
+    }
+    u.quantum.data.iter_mut().for_each(|c| *c = c.conj());
+    for _i in 0..50 {
+        u.step();
+    }
+    for (a, b) in u.quantum.data.iter().zip(initial.data.iter()) {
+        assert!(a.conj().sub(b).radius() < 1e-5);
+    }
+}
+
+#[test]
+fn measurements_read_the_field_at_the_step_time() {
+    let mut u = Universe::new(24, 24);
+    u.setup();
+    u.add_wavepacket(12.0, 12.0, 2.0, 0.8, 0.3, Complex::new(1.0, 0.0));
+    let norm = u.norm();
+    for _i in 0..20 {
+        u.step();
+        assert!((u.norm() - norm).abs() < 1e-4);
+    }
+    // exporting the momentum state and loading it back leaves the field as it was
+    let field = u.quantum.clone();
+    let state = u.momentum_state(false);
+    u.load_momentum_state(&state);
+    for (a, b) in u.quantum.data.iter().zip(field.data.iter()) {
+        assert!(a.sub(b).radius() < 1e-5);
+    }
+}
+
+#[test]
+fn step_conserves_energy_in_a_trap() {
+    let mut u = Universe::new(60, 60);
+    u.setup();
+    u.add_harmonic_potential(Coord::new(30, 30), 0.0, 0.1, 0.1);
+    u.add_coherent_state(25.0, 30.0, 0.1, 0.0, 0.0, Complex::new(1.0, 0.0));
+    let start = u.observables();
+    for _i in 0..100 {
+        u.step();
+    }
+    let end = u.observables();
+    assert!((end.probability - start.probability).abs() < 1e-3);
+    assert!((end.total_energy - start.total_energy).abs() / start.total_energy < 0.02);
+    assert!(end.x > start.x);
+}
+
+#[test]
+fn render_current_size() {
+    let mut u = Universe::new(10, 6);
+    u.add_wavepacket(5.0, 3.0, 1.5, 0.8, 0.0, Complex::new(1.0, 0.0));
+    assert_eq!(u.probability_current().len(), 120);
+    u.render_current(4, 2);
+    assert_eq!(u.frame_len(), 10 * 4 * 6 * 4 * 4);
+}
+
+#[test]
+fn momentum_space_round_trip() {
+    let mut u = Universe::new(16, 12);
+    u.add_wavepacket(8.0, 6.0, 2.0, PI / 2.0, 0.0, Complex::new(1.0, 0.0));
+    let state = u.momentum_state(false);
+    assert_eq!(state.len(), 16 * 12 * 2);
+    u.render_momentum(true);
+    assert_eq!(u.frame_len(), 16 * 12 * 4);
+
+    // k = π/2 is a quarter of the way from the center to the edge of the spectrum
+    let momentum = u.momentum_field(false);
+    let (peak, _) = momentum
+        .data
+        .iter()
+        .enumerate()
+        .fold((0, 0.0), |best, (i, c)| {
+            if c.norm() > best.1 {
+                (i, c.norm())
+            } else {
+                best
+            }
+        });
+    assert_eq!((peak % 16, peak / 16), (12, 6));
+
+    // brightness follows |ψ(k)|², the peak at half brightness
+    u.render_momentum(false);
+    let peak_density = momentum.data[peak].norm();
+    for &i in [peak, peak + 1, peak + 16].iter() {
+        let c = momentum.data[i];
+        let level = 0.5 * c.norm() / peak_density;
+        let expected = Colormap::PhaseDark.complex(Complex::from_polar(level, c.arg()));
+        assert_eq!(
+            u.frame[4 * i..4 * i + 3],
+            [expected.r, expected.g, expected.b]
+        );
+    }
+
+    let original = u.quantum.clone();
+    u.load_momentum_state(&state);
+    for (a, b) in u.quantum.data.iter().zip(original.data.iter()) {
+        assert!(a.sub(b).radius() < 1e-4);
+    }
+}
+
+#[test]
+fn wigner_of_a_cat_state() {
+    let mut u = Universe::new(48, 9);
+    u.add_gaussian(Coord::new(14, 4), 2.0, 0.0, 0.0, 1.0);
+    u.add_gaussian(Coord::new(34, 4), 2.0, 0.0, 0.0, 1.0);
+    let w = u.wigner_function(0.0, 4.0, 47.0, 4.0);
+    assert_eq!(w.len(), 48 * 48);
+    // interference fringes between the two packets dip below zero
+    let middle: Vec<f32> = (0..48).map(|row| w[24 + row * 48]).collect();
+    assert!(middle.iter().any(|&v| v < -0.01));
+    assert!(u.wigner_marginal_error(0.0, 4.0, 47.0, 4.0) < 1e-4);
+    u.render_wigner(0.0, 4.0, 47.0, 4.0);
+    assert_eq!((u.frame_width(), u.frame_height()), (48, 48));
+}
+
+#[test]
+fn wigner_of_a_diagonal_cut() {
+    let mut u = Universe::new(40, 40);
+    u.add_plane_wave(0.5, 0.5, Complex::new(1.0, 0.0));
+    let w = u.wigner_function(5.0, 5.0, 30.0, 30.0);
+    let n = (w.len() as f32).sqrt() as usize;
+    assert_eq!(n, 36);
+    // the momentum along the cut is k·d per cell, not per sample of the stretched cut
+    let peak = (0..n)
+        .max_by(|&a, &b| w[n / 2 + a * n].partial_cmp(&w[n / 2 + b * n]).unwrap())
+        .unwrap();
+    let p = PI / 2.0 - PI * (peak as f32 + 0.5) / n as f32;
+    assert!((p - 0.5 * 2f32.sqrt()).abs() < PI / n as f32, "{}", p);
+}
+
+#[test]
+fn vortices_of_a_laguerre_gauss_mode() {
+    let mut u = Universe::new(30, 30);
+    u.setup();
+    u.add_laguerre_gauss(14.5, 14.5, 5.0, 0, 1, Complex::new(1.0, 0.0));
+    assert_eq!(u.vortices(), vec![14.5, 14.5, 1.0]);
+
+    u.set_vortex_tracking(true, 2.0);
+    for _i in 0..5 {
+        u.step();
+    }
+    assert_eq!(u.vortex_trajectory_count(), 1);
+    assert_eq!(u.vortex_trajectory(0).len(), 10);
+    u.render_vortices(3);
+    assert_eq!(u.frame_len(), 90 * 90 * 4);
+}
+
+#[test]
+fn harmonic_trap_spectrum() {
+    let omega = 0.1;
+    let mut u = Universe::new(48, 48);
+    u.setup();
+    u.add_harmonic_potential(Coord::new(24, 24), 0.0, omega, omega);
+    u.add_coherent_state(21.0, 24.0, omega, 0.0, 0.0, Complex::new(1.0, 0.0));
+    u.set_autocorrelation(true);
+    for _i in 0..2000 {
+        u.step();
+    }
+    let levels = u.energy_eigenvalues(0.0, 1.0, 201);
+    assert!(levels.len() >= 2);
+    // E = ω (n + 1) in two dimensions
+    assert!((levels[0] - omega).abs() < 0.01);
+    assert!((levels[1] - 2.0 * omega).abs() < 0.01);
+}
+
+#[test]
+fn changing_dt_restarts_the_autocorrelation() {
+    let mut u = Universe::new(20, 20);
+    u.setup();
+    u.add_wavepacket(10.0, 10.0, 2.0, 0.5, 0.0, Complex::new(1.0, 0.0));
+    u.set_autocorrelation(true);
+    for _i in 0..10 {
+        u.step();
+    }
+    assert_eq!(u.autocorrelation().len(), 2 * 11);
+    u.set_dt(0.05);
+    let samples = u.autocorrelation();
+    assert_eq!(samples.len(), 2);
+    assert!((samples[0] - u.norm()).abs() < 1e-5);
+    u.set_autocorrelation(false);
+    u.set_dt(0.1);
+    assert!(u.autocorrelation().is_empty());
+}
+
+#[test]
+fn barrier_transmission_matches_analytic() {
+    // a single row between two zero rows behaves as a 1D line
+    let (k, height, width) = (0.5, 0.15, 4.0);
+    let mut u = Universe::new(600, 3);
+    u.setup();
+    u.add_soft_wall(Coord::new(300, 0), Coord::new(303, 2), height, 0.0);
+    u.add_wavepacket(150.0, 1.0, 20.0, k, 0.0, Complex::new(1.0, 0.0));
+    u.set_scattering_zones(300.0, 303.0, false);
+    let mut steps = 0;
+    while !u.scattering_settled(100, 1e-3) && steps < 10000 {
+        u.step();
+        steps += 1;
+    }
+    assert!(u.scattering_settled(100, 1e-3));
+    assert!((u.transmission_coefficient() + u.reflection_coefficient() - 1.0).abs() < 1e-3);
+    // the five point stencil gives the lattice dispersion 1 - cos k rather than k²/2
+    let expected = analytic_transmission(1.0 - k.cos(), height, width);
+    assert!((u.transmission_coefficient() - expected).abs() / expected < 0.03);
+}
+
+#[test]
+fn tracers_follow_a_moving_packet() {
+    let mut u = Universe::new(60, 30);
+    u.setup();
+    u.add_wavepacket(20.0, 15.0, 3.0, 0.5, 0.0, Complex::new(1.0, 0.0));
+    u.seed_tracers_with(50, &mut StdRng::seed_from_u64(7));
+    let start: f32 = u.tracer_positions().iter().step_by(2).sum::<f32>() / 50.0;
+    for _i in 0..100 {
+        u.step();
+    }
+    let end: f32 = u.tracer_positions().iter().step_by(2).sum::<f32>() / 50.0;
+    let packet = u.observables();
+    assert!(end > start + 2.0);
+    assert!((end - packet.x).abs() < 2.0);
+}
+
+#[test]
+fn render_into_a_stable_frame_buffer() {
+    let mut u = Universe::new(6, 4);
+    let (pointer, generation) = (u.frame_ptr(), u.frame_generation());
+    assert_eq!(u.frame_len(), 6 * 4 * 4);
+    u.walls.set(Coord::new(1, 1), true);
+    u.render();
+    u.render();
+    assert_eq!((u.frame_ptr(), u.frame_generation()), (pointer, generation));
+    // same number of bytes laid out in other dimensions
+    u.render_resampled(4, 6, Interpolation::Nearest);
+    assert_eq!(u.frame_len(), 6 * 4 * 4);
+    assert_ne!(u.frame_generation(), generation);
+    u.render();
+    let wall = Color::wall();
+    assert_eq!(
+        &u.frame[(1 + 6) * 4..(1 + 6) * 4 + 4],
+        &[wall.r, wall.g, wall.b, 255]
+    );
+}
+
+#[test]
+fn render_with_a_selected_colormap() {
+    let mut u = Universe::new(4, 4);
+    u.render();
+    assert_eq!(&u.frame[..4], &[0, 0, 0, 255]);
+    u.set_colormap(Colormap::PhaseLight);
+    u.render();
+    assert_eq!(&u.frame[..4], &[255, 255, 255, 255]);
+    let terminal = format!("{}", u.potential_level.colored(Colormap::Viridis));
+    assert_eq!(terminal.lines().count(), 4);
+}
+
+#[test]
+fn log_scaling_reveals_small_amplitudes() {
+    let mut u = Universe::new(4, 4);
+    u.quantum.set(Coord::new(1, 1), Complex::new(1e-3, 0.0));
+    u.render();
+    let linear = u.frame[(1 + 4) * 4..(1 + 4) * 4 + 3].to_vec();
+    u.set_log_scaling(2.0, 4.0);
+    u.render();
+    assert!(u.frame[(1 + 4) * 4 + 1] > linear[1] + 50);
+
+    u.set_auto_exposure(1.0, 1.0);
+    u.render();
+    assert!((u.exposure() - 1e-3).abs() < 1e-6);
+}
+
+#[test]
+fn render_composited_overlays() {
+    let mut u = Universe::new(4, 3);
+    u.sinks.set(Coord::new(2, 1), true);
+    u.set_sink_layer(SinkStyle::Tint, Color::new(255, 0, 0), 1.0);
+    u.set_cell_grid(true, Color::white(), 1.0);
+    u.set_cell_size(3);
+    u.render();
+    assert_eq!(u.frame_len(), u.frame_width() * u.frame_height() * 4);
+    let at = |x: usize, y: usize| &u.frame[(x + y * 12) * 4..(x + y * 12) * 4 + 3];
+    assert_eq!(at(7, 4), &[255, 0, 0]);
+    assert_eq!(at(6, 4), &[255, 255, 255]);
+    assert_eq!(at(4, 4), &[0, 0, 0]);
+}
+
+#[test]
+fn render_resampled_to_any_size() {
+    let mut u = Universe::new(8, 6);
+    u.add_wavepacket(4.0, 3.0, 1.5, 0.5, 0.0, Complex::new(1.0, 0.0));
+    u.walls.set(Coord::new(0, 0), true);
+    u.render_resampled(50, 31, Interpolation::Bicubic);
+    assert_eq!((u.frame_width(), u.frame_height()), (50, 31));
+    assert_eq!(u.frame_len(), 50 * 31 * 4);
+    let wall = Color::wall();
+    assert_eq!(&u.frame[..4], &[wall.r, wall.g, wall.b, 255]);
+}
+
+#[test]
+fn export_frames_and_fields_as_png() {
+    let mut u = Universe::new(6, 4);
+    u.set_cell_size(2);
+    u.render();
+    let frame = u.png(Image::Frame).unwrap();
+    assert_eq!(&frame[1..4], b"PNG");
+    assert_eq!(&frame[16..24], &[0, 0, 0, 12, 0, 0, 0, 8]);
+    let potential = u.png(Image::PotentialLevel).unwrap();
+    assert_eq!(&potential[16..24], &[0, 0, 0, 6, 0, 0, 0, 4]);
+
+    let path = std::env::temp_dir().join("quantum-wave-density.png");
+    u.save_png(Image::Density, &path).unwrap();
+    assert_eq!(
+        std::fs::read(&path).unwrap(),
+        u.png(Image::Density).unwrap()
+    );
+    std::fs::remove_file(&path).unwrap();
+
+    u.render_resampled(0, 0, Interpolation::Nearest);
+    assert!(u.png(Image::Frame).is_err());
+    assert!(u.png(Image::Density).is_ok());
+}
+
+#[test]
+fn record_every_few_steps() {
+    let mut u = Universe::new(8, 6);
+    u.setup();
+    u.add_wavepacket(4.0, 3.0, 1.5, 0.5, 0.0, Complex::new(1.0, 0.0));
+    u.start_recording(VideoFormat::Y4m, 3, 30);
+    for _ in 0..7 {
+        u.step();
+    }
+    assert_eq!(u.recorded_frames(), 3);
+    let bytes = u.finish_recording();
+    let header = b"YUV4MPEG2 W8 H6 F30:1 Ip A1:1 C444\n";
+    assert_eq!(bytes.len(), header.len() + 3 * (6 + 8 * 6 * 3));
+    assert_eq!(u.recorded_frames(), 0);
+    assert!(u.finish_recording().is_empty());
+}
+
+#[test]
+fn tilt_pulls_the_field() {
+    let mut u = Universe::new(24, 12);
+    u.setup();
+    u.add_wavepacket(12.0, 6.0, 2.0, 0.0, 0.0, Complex::new(1.0, 0.0));
+    u.set_tilt(1.0, 0.0);
+    for _ in 0..40 {
+        u.step();
+    }
+    let observables = u.observables();
+    assert!(observables.px > 0.1 && observables.x > 12.0);
+    assert!(
+        observables.py.abs() < 0.1 * observables.px,
+        "{:?}",
+        observables
+    );
+    assert_eq!(u.terminal_frame(Palette::Monochrome).lines().count(), 6);
+}
+
+#[test]
+fn export_isolines_as_svg() {
+    let mut u = Universe::new(16, 12);
+    u.setup();
+    u.add_potential_barrier(Coord::new(8, 6), 0.0, 3.0, 0.5);
+    u.walls.set(Coord::new(2, 2), true);
+    u.render();
+    let isolines = u.isolines(Image::PotentialLevel, 0.25);
+    assert_eq!(isolines.len(), 2);
+    assert!(isolines
+        .iter()
+        .flatten()
+        .all(|&(x, _)| (x - 7.0).abs() < 0.6 || (x - 10.0).abs() < 0.6));
+    assert!(u.isolines(Image::Frame, 0.25).is_empty());
+
+    let svg = u.svg(Image::PotentialLevel, vec![0.1, 0.25, 0.4], 4.0);
+    assert!(svg.contains("viewBox=\"0 0 16 12\""));
+    assert_eq!(svg.matches("<image ").count(), 1);
+    assert_eq!(svg.matches("<polyline ").count(), 6 + 1);
+
+    u.render_resampled(0, 0, Interpolation::Nearest);
+    let svg = u.svg(Image::PotentialLevel, vec![0.25], 4.0);
+    assert_eq!(svg.matches("<image ").count(), 0);
+    assert_eq!(svg.matches("<polyline ").count(), 2 + 1);
+}
+
+#[test]
+fn legends_match_the_rendering() {
+    let mut u = Universe::new(8, 6);
+    u.setup();
+    u.add_potential_barrier(Coord::new(4, 3), 0.0, 2.0, 0.5);
+    assert_eq!(u.phase_wheel(16).len(), 16 * 16 * 4);
+    let wheel = u.phase_wheel_png(16).unwrap();
+    assert_eq!(&wheel[16..24], &[0, 0, 0, 16, 0, 0, 0, 16]);
+    assert!(u.phase_wheel_png(0).is_err());
+
+    u.set_colormap(Colormap::Viridis);
+    let bar = u.colorbar(Image::Frame, 30, 20);
+    assert_eq!(bar.len(), 30 * 20 * 4);
+    let lowest = Colormap::Viridis.scalar(0.0);
+    let highest = Colormap::Viridis.scalar(1.0);
+    assert_eq!(&bar[..3], &[lowest.r, lowest.g, lowest.b]);
+    assert_eq!(&bar[4 * 29..4 * 29 + 3], &[highest.r, highest.g, highest.b]);
+
+    // the ends of the potential bar are the colors of its lowest and highest cells
+    let potential = u.colorbar(Image::PotentialLevel, 30, 20);
+    let field = u.potential_level.clone();
+    let colors = field.colors(Colormap::Viridis, &u.scaling);
+    let cell_color = |pick: fn(f32, f32) -> f32| {
+        let value = field.data.iter().cloned().fold(field.data[0], pick);
+        colors.data[field.data.iter().position(|&v| v == value).unwrap()]
+    };
+    let (lowest, highest) = (cell_color(f32::min), cell_color(f32::max));
+    assert_ne!(lowest, highest);
+    assert_eq!(&potential[..3], &[lowest.r, lowest.g, lowest.b]);
+    assert_eq!(
+        &potential[4 * 29..4 * 29 + 3],
+        &[highest.r, highest.g, highest.b]
+    );
+    let png = u.colorbar_png(Image::PotentialLevel, 30, 20).unwrap();
+    assert_eq!(&png[16..24], &[0, 0, 0, 30, 0, 0, 0, 20]);
+    assert!(u.colorbar(Image::PotentialLevel, 0, 20).is_empty());
+    assert!(u.colorbar_png(Image::PotentialLevel, 0, 20).is_err());
+    assert!(u.colorbar_png(Image::Frame, 30, 0).is_err());
+}
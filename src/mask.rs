@@ -0,0 +1,98 @@
+extern crate wasm_bindgen;
+
+use coord::Coord;
+use grid::Grid;
+use wasm_bindgen::prelude::*;
+
+/// Boolean selection of cells, built from JavaScript to restrict where potentials are applied.
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct Mask {
+    grid: Grid<bool>,
+}
+
+/// Public methods, exported to JavaScript.
+#[wasm_bindgen]
+impl Mask {
+    pub fn new(width: usize, height: usize) -> Mask {
+        Mask {
+            grid: Grid::<bool>::new(width, height),
+        }
+    }
+
+    /// Select or deselect a single cell, ignoring coords outside the mask.
+    pub fn set(&mut self, x: i32, y: i32, value: bool) {
+        let coord = Coord::new(x, y);
+        if self.grid.is_valid_coord(&coord) {
+            self.grid.set(coord, value);
+        }
+    }
+
+    /// Check if the cell is selected.
+    pub fn get(&self, x: i32, y: i32) -> bool {
+        *self.grid.get(Coord::new(x, y)).unwrap_or(&false)
+    }
+
+    /// Select every cell of the rectangle spanned by two corners, inclusive.
+    pub fn add_rect(&mut self, x0: i32, y0: i32, x1: i32, y1: i32) {
+        for y in y0.min(y1)..=y0.max(y1) {
+            for x in x0.min(x1)..=x0.max(x1) {
+                self.set(x, y, true);
+            }
+        }
+    }
+
+    /// Select every cell within radius of the center.
+    pub fn add_circle(&mut self, cx: i32, cy: i32, radius: f32) {
+        let r = radius.ceil() as i32;
+        for y in cy - r..=cy + r {
+            for x in cx - r..=cx + r {
+                let (dx, dy) = ((x - cx) as f32, (y - cy) as f32);
+                if dx.hypot(dy) <= radius {
+                    self.set(x, y, true);
+                }
+            }
+        }
+    }
+
+    /// Swap selected and unselected cells.
+    pub fn invert(&mut self) {
+        for cell in self.grid.data.iter_mut() {
+            *cell = !*cell;
+        }
+    }
+
+    /// Deselect all cells.
+    pub fn clear(&mut self) {
+        self.grid = Grid::<bool>::new(self.grid.width, self.grid.height);
+    }
+}
+
+impl Mask {
+    /// Underlying boolean grid.
+    pub fn grid(&self) -> &Grid<bool> {
+        &self.grid
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn add_rect_to_mask() {
+    let mut mask = Mask::new(5, 5);
+    mask.add_rect(3, 1, 1, 2);
+    assert!(mask.get(1, 1));
+    assert!(mask.get(3, 2));
+    assert!(!mask.get(0, 0));
+    assert!(!mask.get(3, 3));
+    assert_eq!(mask.grid().data.iter().filter(|&&c| c).count(), 6);
+}
+
+#[test]
+fn add_circle_to_mask_clips_to_grid() {
+    let mut mask = Mask::new(5, 5);
+    mask.add_circle(0, 0, 1.0);
+    assert!(mask.get(0, 0) && mask.get(1, 0) && mask.get(0, 1));
+    assert!(!mask.get(1, 1));
+    mask.invert();
+    assert!(mask.get(1, 1) && !mask.get(0, 0));
+}
@@ -0,0 +1,121 @@
+extern crate wasm_bindgen;
+
+use complex::Complex;
+use coord::Coord;
+use grid::Grid;
+use wasm_bindgen::prelude::*;
+
+/// Expectation values of the quantum field, in units where ħ = m = 1 and cells are 1 wide.
+/// Derivatives use the same five point stencil as the time step, with zero outside the grid.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Observables {
+    pub probability: f32,
+    pub x: f32,
+    pub y: f32,
+    pub px: f32,
+    pub py: f32,
+    pub kinetic_energy: f32,
+    pub potential_energy: f32,
+    pub total_energy: f32,
+    pub dx: f32,
+    pub dy: f32,
+    pub dpx: f32,
+    pub dpy: f32,
+}
+
+impl Observables {
+    /// Measure the field against the given potential.
+    /// All values but the probability are normalized by it; a null field measures as zero.
+    pub fn measure(quantum: &Grid<Complex>, potential: &Grid<f32>) -> Observables {
+        let at = |coord: Coord| *quantum.get(coord).unwrap_or(&Complex::zero());
+        let (mut p, mut x, mut y, mut x2, mut y2) = (0.0, 0.0, 0.0, 0.0, 0.0);
+        let (mut px, mut py, mut px2, mut py2, mut v) = (0.0, 0.0, 0.0, 0.0, 0.0);
+
+        for j in 0..quantum.height {
+            for i in 0..quantum.width {
+                let coord = Coord::new(i as i32, j as i32);
+                let psi = at(coord);
+                let density = psi.norm();
+                if density == 0.0 {
+                    continue;
+                }
+                let (fi, fj) = (i as f32, j as f32);
+                let (left, right) = (at(coord.left()), at(coord.right()));
+                let (top, bottom) = (at(coord.top()), at(coord.bottom()));
+                let conj = psi.conj();
+
+                p += density;
+                x += fi * density;
+                y += fj * density;
+                x2 += fi * fi * density;
+                y2 += fj * fj * density;
+                // <p> = Im(ψ* ∂ψ) and <p²> = -Re(ψ* ∂²ψ)
+                px += conj.mul(&right.sub(&left)).im * 0.5;
+                py += conj.mul(&bottom.sub(&top)).im * 0.5;
+                px2 -= conj.mul(&right.add(&left).sub(&psi.scale(2.0))).re;
+                py2 -= conj.mul(&bottom.add(&top).sub(&psi.scale(2.0))).re;
+                v += potential.get(coord).unwrap() * density;
+            }
+        }
+
+        if p == 0.0 {
+            return Observables::default();
+        }
+        let (x, y, x2, y2) = (x / p, y / p, x2 / p, y2 / p);
+        let (px, py, px2, py2) = (px / p, py / p, px2 / p, py2 / p);
+        let kinetic_energy = 0.5 * (px2 + py2);
+        let potential_energy = v / p;
+        Observables {
+            probability: p,
+            x,
+            y,
+            px,
+            py,
+            kinetic_energy,
+            potential_energy,
+            total_energy: kinetic_energy + potential_energy,
+            dx: (x2 - x * x).max(0.0).sqrt(),
+            dy: (y2 - y * y).max(0.0).sqrt(),
+            dpx: (px2 - px * px).max(0.0).sqrt(),
+            dpy: (py2 - py * py).max(0.0).sqrt(),
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn measure_null_field() {
+    let quantum = Grid::<Complex>::new(4, 4);
+    let potential = Grid::<f32>::new(4, 4);
+    assert_eq!(
+        Observables::measure(&quantum, &potential),
+        Observables::default()
+    );
+}
+
+#[test]
+fn measure_moving_gaussian() {
+    let (sigma, k) = (3.0, 0.5);
+    let mut quantum = Grid::<Complex>::new(50, 40);
+    let mut potential = Grid::<f32>::new(50, 40);
+    for j in 0..40 {
+        for i in 0..50 {
+            let coord = Coord::new(i, j);
+            let (dx, dy) = (i as f32 - 20.0, j as f32 - 18.0);
+            let envelope = (-(dx * dx + dy * dy) / (4.0 * sigma * sigma)).exp();
+            quantum.set(coord, Complex::from_polar(envelope, k * dx));
+            potential.set(coord, -1.0);
+        }
+    }
+    let obs = Observables::measure(&quantum, &potential);
+    assert!((obs.x - 20.0).abs() < 1e-3 && (obs.y - 18.0).abs() < 1e-3);
+    assert!((obs.dx - sigma).abs() < 1e-2 && (obs.dy - sigma).abs() < 1e-2);
+    // the central difference underestimates momentum by a few percent at this k
+    assert!((obs.px - k).abs() / k < 0.06 && obs.py.abs() < 1e-4);
+    assert!((obs.dpy - 0.5 / sigma).abs() < 0.01);
+    let expected_kinetic = 0.5 * (k * k + 2.0 * 0.25 / (sigma * sigma));
+    assert!((obs.kinetic_energy - expected_kinetic).abs() / expected_kinetic < 0.05);
+    assert!((obs.potential_energy + 1.0).abs() < 1e-5);
+    assert_eq!(obs.total_energy, obs.kinetic_energy + obs.potential_energy);
+}
@@ -0,0 +1,144 @@
+//! Library of wavepackets and modes.
+//! Each function takes the offset (dx, dy) of a cell from the packet center and returns
+//! the normalized amplitude at that cell, in units where ħ = m = 1 and cells are 1 wide.
+
+use complex::Complex;
+use std::f32::consts::PI;
+
+/// Gaussian packet with position spread sigma carrying the wavevector (kx, ky).
+pub fn gaussian(dx: f32, dy: f32, sigma: f32, kx: f32, ky: f32) -> Complex {
+    let a = (2.0 * PI * sigma * sigma).powf(-0.5);
+    let envelope = a * (-(dx * dx + dy * dy) / (4.0 * sigma * sigma)).exp();
+    Complex::from_polar(envelope, kx * dx + ky * dy)
+}
+
+/// Unit amplitude plane wave with wavevector (kx, ky).
+pub fn plane_wave(x: f32, y: f32, kx: f32, ky: f32) -> Complex {
+    Complex::from_polar(1.0, kx * x + ky * y)
+}
+
+/// Hermite–Gauss mode of orders (m, n) and waist w.
+pub fn hermite_gauss(dx: f32, dy: f32, waist: f32, m: u32, n: u32) -> Complex {
+    let mode = |x: f32, order: u32| {
+        let norm =
+            (2.0 / PI).powf(0.25) / (2f32.powi(order as i32) * factorial(order) * waist).sqrt();
+        norm * hermite(order, 2f32.sqrt() * x / waist) * (-x * x / (waist * waist)).exp()
+    };
+    Complex::new(mode(dx, m) * mode(dy, n), 0.0)
+}
+
+/// Laguerre–Gauss mode of radial order p, winding number l and waist w.
+pub fn laguerre_gauss(dx: f32, dy: f32, waist: f32, p: u32, l: i32) -> Complex {
+    let abs_l = l.unsigned_abs();
+    let norm = (2.0 * factorial(p) / (PI * factorial(p + abs_l))).sqrt() / waist;
+    let r2 = (dx * dx + dy * dy) / (waist * waist);
+    let radial =
+        norm * (2.0 * r2).sqrt().powi(abs_l as i32) * laguerre(p, abs_l, 2.0 * r2) * (-r2).exp();
+    Complex::from_polar(radial, l as f32 * dy.atan2(dx))
+}
+
+/// Coherent state of a harmonic trap of frequency omega, moving with momentum (px, py).
+/// It is the trap ground state displaced to the packet center.
+pub fn coherent_state(dx: f32, dy: f32, omega: f32, px: f32, py: f32) -> Complex {
+    gaussian(dx, dy, (0.5 / omega).sqrt(), px, py)
+}
+
+/// Physicists' Hermite polynomial H_n(x).
+fn hermite(n: u32, x: f32) -> f32 {
+    let (mut prev, mut current) = (1.0, 2.0 * x);
+    if n == 0 {
+        return prev;
+    }
+    for k in 1..n {
+        let next = 2.0 * x * current - 2.0 * k as f32 * prev;
+        prev = current;
+        current = next;
+    }
+    current
+}
+
+/// Generalized Laguerre polynomial L_p^alpha(x).
+fn laguerre(p: u32, alpha: u32, x: f32) -> f32 {
+    let alpha = alpha as f32;
+    let (mut prev, mut current) = (1.0, 1.0 + alpha - x);
+    if p == 0 {
+        return prev;
+    }
+    for k in 1..p {
+        let k = k as f32;
+        let next = ((2.0 * k + 1.0 + alpha - x) * current - (k + alpha) * prev) / (k + 1.0);
+        prev = current;
+        current = next;
+    }
+    current
+}
+
+fn factorial(n: u32) -> f32 {
+    (1..=n).map(|k| k as f32).product()
+}
+
+#[cfg(test)]
+fn overlap<F: Fn(f32, f32) -> Complex, G: Fn(f32, f32) -> Complex>(f: F, g: G) -> Complex {
+    let mut sum = Complex::zero();
+    for y in -40..=40 {
+        for x in -40..=40 {
+            let (x, y) = (x as f32, y as f32);
+            sum = sum.add(&f(x, y).conj().mul(&g(x, y)));
+        }
+    }
+    sum
+}
+
+#[cfg(test)]
+#[test]
+fn gaussian_is_normalized_and_carries_momentum() {
+    let norm = overlap(
+        |x, y| gaussian(x, y, 3.0, 0.5, 0.0),
+        |x, y| gaussian(x, y, 3.0, 0.5, 0.0),
+    );
+    assert!((norm.re - 1.0).abs() < 1e-4);
+    let phase_step =
+        gaussian(1.0, 0.0, 3.0, 0.5, 0.0).arg() - gaussian(0.0, 0.0, 3.0, 0.5, 0.0).arg();
+    assert!((phase_step - 0.5).abs() < 1e-6);
+}
+
+#[test]
+fn plane_wave_has_unit_modulus() {
+    let c = plane_wave(3.0, -2.0, 0.7, 0.2);
+    assert!((c.radius() - 1.0).abs() < 1e-6);
+    assert!((c.arg() - 1.7).abs() < 1e-6);
+}
+
+#[test]
+fn hermite_polynomials() {
+    assert_eq!(hermite(0, 0.7), 1.0);
+    assert_eq!(hermite(2, 1.0), 2.0);
+    assert_eq!(hermite(3, 2.0), 40.0);
+}
+
+#[test]
+fn hermite_gauss_modes_are_orthonormal() {
+    let hg10 = |x, y| hermite_gauss(x, y, 5.0, 1, 0);
+    let hg01 = |x, y| hermite_gauss(x, y, 5.0, 0, 1);
+    assert!((overlap(hg10, hg10).re - 1.0).abs() < 1e-3);
+    let hg21 = |x, y| hermite_gauss(x, y, 5.0, 2, 1);
+    assert!((overlap(hg21, hg21).re - 1.0).abs() < 1e-3);
+    assert!(overlap(hg10, hg01).radius() < 1e-4);
+}
+
+#[test]
+fn laguerre_gauss_vortex_is_normalized_and_winds() {
+    let lg = |x, y| laguerre_gauss(x, y, 5.0, 1, 2);
+    assert!((overlap(lg, lg).re - 1.0).abs() < 1e-3);
+    assert_eq!(laguerre_gauss(0.0, 0.0, 5.0, 1, 2).radius(), 0.0);
+    let east = laguerre_gauss(3.0, 0.0, 5.0, 0, 1).arg();
+    let north = laguerre_gauss(0.0, 3.0, 5.0, 0, 1).arg();
+    assert!((north - east - PI / 2.0).abs() < 1e-6);
+}
+
+#[test]
+fn coherent_state_matches_trap_ground_state_width() {
+    let omega = 0.1;
+    let c = coherent_state(0.0, 0.0, omega, 0.0, 0.0);
+    assert!((c.norm() - omega / PI).abs() < 1e-6);
+}
@@ -0,0 +1,161 @@
+//! Minimal PNG encoder for r, g, b, a images.
+//! Image data is stored in uncompressed deflate blocks inside the zlib stream,
+//! which every decoder reads and which needs no compression library.
+
+use std::io::{self, Write};
+
+const SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+/// Largest payload of a stored deflate block.
+const MAX_STORED_BLOCK: usize = 65535;
+
+/// Write width by height pixels given as r, g, b, a bytes, row after row.
+/// PNG has no empty images, so a zero width or height is an invalid input.
+pub fn write<W: Write>(mut writer: W, width: usize, height: usize, rgba: &[u8]) -> io::Result<()> {
+    if width == 0 || height == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "PNG images need a non-zero width and height",
+        ));
+    }
+    assert_eq!(
+        rgba.len(),
+        width * height * 4,
+        "pixel data does not match the size"
+    );
+    writer.write_all(&SIGNATURE)?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    // 8 bits per channel, truecolor with alpha, deflate, adaptive filtering, no interlace
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+    write_chunk(&mut writer, b"IHDR", &header)?;
+
+    // every scanline starts with its filter type, none here
+    let mut scanlines = Vec::with_capacity(height * (width * 4 + 1));
+    for row in rgba.chunks(width * 4).take(height) {
+        scanlines.push(0);
+        scanlines.extend_from_slice(row);
+    }
+    write_chunk(&mut writer, b"IDAT", &zlib_stored(&scanlines))?;
+    write_chunk(&mut writer, b"IEND", &[])
+}
+
+fn write_chunk<W: Write>(writer: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(kind)?;
+    writer.write_all(data)?;
+    let crc = crc32(&[&kind[..], data].concat());
+    writer.write_all(&crc.to_be_bytes())
+}
+
+/// Zlib stream made of stored deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let blocks = data.len() / MAX_STORED_BLOCK + 1;
+    let mut stream = Vec::with_capacity(data.len() + blocks * 5 + 6);
+    // deflate with a 32K window, no dictionary, check bits making the header a multiple of 31
+    stream.extend_from_slice(&[0x78, 0x01]);
+    let mut chunks = data.chunks(MAX_STORED_BLOCK).peekable();
+    if chunks.peek().is_none() {
+        stream.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(chunk) = chunks.next() {
+        let last = chunks.peek().is_none();
+        let length = chunk.len() as u16;
+        stream.push(last as u8);
+        stream.extend_from_slice(&length.to_le_bytes());
+        stream.extend_from_slice(&(!length).to_le_bytes());
+        stream.extend_from_slice(chunk);
+    }
+    stream.extend_from_slice(&adler32(data).to_be_bytes());
+    stream
+}
+
+/// CRC-32 as used by PNG chunks, reflected polynomial 0xEDB88320.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+#[test]
+fn checksums_of_known_strings() {
+    assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+}
+
+#[test]
+fn encode_a_small_image() {
+    let rgba: Vec<u8> = (0..3 * 2 * 4).map(|i| i as u8).collect();
+    let mut png = Vec::new();
+    write(&mut png, 3, 2, &rgba).unwrap();
+    assert_eq!(&png[..8], &SIGNATURE);
+    assert_eq!(&png[12..16], b"IHDR");
+    assert_eq!(&png[16..24], &[0, 0, 0, 3, 0, 0, 0, 2]);
+
+    // walk the chunks, checking their crc and collecting the image data
+    let mut offset = 8;
+    let mut idat = Vec::new();
+    while offset < png.len() {
+        let length = u32::from_be_bytes([
+            png[offset],
+            png[offset + 1],
+            png[offset + 2],
+            png[offset + 3],
+        ]) as usize;
+        let body = &png[offset + 4..offset + 8 + length];
+        let crc = &png[offset + 8 + length..offset + 12 + length];
+        assert_eq!(crc, &crc32(body).to_be_bytes());
+        if &body[..4] == b"IDAT" {
+            idat.extend_from_slice(&body[4..]);
+        }
+        offset += 12 + length;
+    }
+    // a single final stored block holding two filtered scanlines
+    assert_eq!(&idat[..3], &[0x78, 0x01, 1]);
+    assert_eq!(&idat[7..8], &[0]);
+    assert_eq!(&idat[8..20], &rgba[..12]);
+    assert_eq!(&idat[20..21], &[0]);
+    assert_eq!(&idat[21..33], &rgba[12..]);
+}
+
+#[test]
+fn reject_empty_images() {
+    let mut png = Vec::new();
+    let error = write(&mut png, 0, 3, &[]).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    assert_eq!(
+        write(&mut png, 3, 0, &[]).unwrap_err().kind(),
+        io::ErrorKind::InvalidInput
+    );
+    assert!(png.is_empty());
+}
+
+#[test]
+fn split_large_data_in_stored_blocks() {
+    let data = vec![7u8; MAX_STORED_BLOCK + 10];
+    let stream = zlib_stored(&data);
+    assert_eq!(stream.len(), 2 + 2 * 5 + data.len() + 4);
+    assert_eq!(stream[2], 0);
+    assert_eq!(stream[2 + 5 + MAX_STORED_BLOCK], 1);
+}
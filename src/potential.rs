@@ -0,0 +1,152 @@
+//! Library of standard potential shapes.
+//! Each function takes the offset (u, v) of a cell from the potential origin,
+//! already rotated into the potential frame, and returns the potential at that cell.
+
+use std::f32::consts::PI;
+
+/// Rotate an offset by -angle so that u runs along the potential orientation.
+pub fn rotate(dx: f32, dy: f32, angle: f32) -> (f32, f32) {
+    let (sin, cos) = angle.sin_cos();
+    (dx * cos + dy * sin, -dx * sin + dy * cos)
+}
+
+/// Anisotropic 2D harmonic oscillator with angular frequencies omega_u and omega_v.
+pub fn harmonic(u: f32, v: f32, omega_u: f32, omega_v: f32) -> f32 {
+    0.5 * (omega_u * omega_u * u * u + omega_v * omega_v * v * v)
+}
+
+/// Finite step of the given height for u >= 0.
+pub fn step(u: f32, height: f32) -> f32 {
+    if u >= 0.0 {
+        height
+    } else {
+        0.0
+    }
+}
+
+/// Rectangular barrier of the given width centered on u = 0, infinite along v.
+pub fn barrier(u: f32, width: f32, height: f32) -> f32 {
+    if u.abs() <= width / 2.0 {
+        height
+    } else {
+        0.0
+    }
+}
+
+/// Quartic double well with minima at u = ±separation/2 and a central hump of barrier_height.
+/// Flat for a non-positive separation.
+pub fn double_well(u: f32, separation: f32, barrier_height: f32) -> f32 {
+    if separation <= 0.0 {
+        return 0.0;
+    }
+    let a = separation / 2.0;
+    let x = u * u / (a * a) - 1.0;
+    barrier_height * x * x
+}
+
+/// Attractive Coulomb potential, softened to stay finite at the origin.
+pub fn soft_coulomb(u: f32, v: f32, charge: f32, softening: f32) -> f32 {
+    -charge / (u * u + v * v + softening * softening).sqrt()
+}
+
+/// Kronig–Penney lattice of barriers of the given width repeating every period along u.
+/// Flat for a non-positive period.
+pub fn kronig_penney(u: f32, period: f32, width: f32, height: f32) -> f32 {
+    if period <= 0.0 {
+        return 0.0;
+    }
+    let phase = u.rem_euclid(period);
+    if phase < width {
+        height
+    } else {
+        0.0
+    }
+}
+
+/// Egg-crate lattice with the given period along both u and v, oscillating around zero.
+/// Flat for a non-positive period.
+pub fn egg_crate(u: f32, v: f32, period: f32, depth: f32) -> f32 {
+    if period <= 0.0 {
+        return 0.0;
+    }
+    -depth * (2.0 * PI * u / period).cos() * (2.0 * PI * v / period).cos()
+}
+
+#[cfg(test)]
+#[test]
+fn rotate_by_a_quarter_turn() {
+    let (u, v) = rotate(0.0, 1.0, PI / 2.0);
+    assert!((u - 1.0).abs() < 1e-6 && v.abs() < 1e-6);
+}
+
+#[test]
+fn harmonic_is_quadratic_and_anisotropic() {
+    assert_eq!(harmonic(0.0, 0.0, 1.0, 2.0), 0.0);
+    assert_eq!(
+        harmonic(2.0, 0.0, 1.0, 2.0),
+        4.0 * harmonic(1.0, 0.0, 1.0, 2.0)
+    );
+    assert_eq!(
+        harmonic(0.0, 1.0, 1.0, 2.0),
+        4.0 * harmonic(1.0, 0.0, 1.0, 2.0)
+    );
+    assert_eq!(harmonic(-3.0, 1.5, 1.0, 2.0), harmonic(3.0, -1.5, 1.0, 2.0));
+}
+
+#[test]
+fn step_switches_on_at_origin() {
+    assert_eq!(step(-0.5, 2.0), 0.0);
+    assert_eq!(step(0.0, 2.0), 2.0);
+    assert_eq!(step(10.0, 2.0), 2.0);
+}
+
+#[test]
+fn barrier_has_finite_width() {
+    assert_eq!(barrier(0.0, 4.0, 1.0), 1.0);
+    assert_eq!(barrier(-2.0, 4.0, 1.0), 1.0);
+    assert_eq!(barrier(2.5, 4.0, 1.0), 0.0);
+}
+
+#[test]
+fn double_well_has_two_minima_and_a_hump() {
+    assert_eq!(double_well(0.0, 10.0, 3.0), 3.0);
+    assert_eq!(double_well(5.0, 10.0, 3.0), 0.0);
+    assert_eq!(double_well(-5.0, 10.0, 3.0), 0.0);
+    assert!(double_well(2.5, 10.0, 3.0) < 3.0);
+    assert!(double_well(7.0, 10.0, 3.0) > 0.0);
+}
+
+#[test]
+fn soft_coulomb_is_finite_and_radial() {
+    assert_eq!(soft_coulomb(0.0, 0.0, 2.0, 0.5), -4.0);
+    assert_eq!(soft_coulomb(3.0, 4.0, 2.0, 0.0), -0.4);
+    assert_eq!(
+        soft_coulomb(3.0, 4.0, 2.0, 0.5),
+        soft_coulomb(-4.0, 3.0, 2.0, 0.5)
+    );
+    assert!(soft_coulomb(1.0, 0.0, 2.0, 0.5) > soft_coulomb(0.0, 0.0, 2.0, 0.5));
+}
+
+#[test]
+fn kronig_penney_is_periodic() {
+    assert_eq!(kronig_penney(0.0, 5.0, 2.0, 1.0), 1.0);
+    assert_eq!(kronig_penney(3.0, 5.0, 2.0, 1.0), 0.0);
+    assert_eq!(kronig_penney(-4.0, 5.0, 2.0, 1.0), 1.0);
+    assert_eq!(kronig_penney(11.0, 5.0, 2.0, 1.0), 1.0);
+}
+
+#[test]
+fn egg_crate_is_periodic_in_both_directions() {
+    assert_eq!(egg_crate(0.0, 0.0, 4.0, 1.0), -1.0);
+    assert!((egg_crate(2.0, 0.0, 4.0, 1.0) - 1.0).abs() < 1e-6);
+    assert!((egg_crate(4.0, 8.0, 4.0, 1.0) + 1.0).abs() < 1e-5);
+    assert!(egg_crate(1.0, 0.0, 4.0, 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn degenerate_lengths_give_a_flat_potential() {
+    assert_eq!(double_well(1.0, 0.0, 3.0), 0.0);
+    assert_eq!(kronig_penney(1.0, 0.0, 1.0, 2.0), 0.0);
+    assert_eq!(kronig_penney(1.0, -4.0, 1.0, 2.0), 0.0);
+    assert_eq!(egg_crate(1.0, 2.0, 0.0, 1.0), 0.0);
+}
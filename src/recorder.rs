@@ -0,0 +1,133 @@
+//! Capture of rendered frames into an animation.
+
+use gif;
+use std::io;
+use wasm_bindgen::prelude::*;
+
+/// Container of a recording.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VideoFormat {
+    /// Animated GIF looping forever, 256 colors per frame.
+    Gif,
+    /// Uncompressed YUV4MPEG2 stream with full resolution 4:4:4 chroma, for external encoders.
+    Y4m,
+}
+
+/// Frames encoded as they are captured, every interval steps.
+#[derive(Clone, Debug)]
+pub struct Recorder {
+    format: VideoFormat,
+    interval: usize,
+    fps: u32,
+    steps: usize,
+    /// Size of the first frame; frames of another size are skipped.
+    size: Option<(usize, usize)>,
+    pub frames: usize,
+    bytes: Vec<u8>,
+}
+
+impl Recorder {
+    pub fn new(format: VideoFormat, interval: usize, fps: u32) -> Self {
+        Recorder {
+            format,
+            interval: interval.max(1),
+            fps: fps.max(1),
+            steps: 0,
+            size: None,
+            frames: 0,
+            bytes: Vec::new(),
+        }
+    }
+
+    /// Count a step, returning true when a frame is due.
+    pub fn tick(&mut self) -> bool {
+        self.steps += 1;
+        self.steps.is_multiple_of(self.interval)
+    }
+
+    /// Encode a frame of r, g, b, a pixels.
+    /// Frames too large for the format are an error and leave the recording unchanged.
+    pub fn capture(&mut self, width: usize, height: usize, rgba: &[u8]) -> io::Result<()> {
+        match self.size {
+            None => {
+                match self.format {
+                    VideoFormat::Gif => gif::write_header(&mut self.bytes, width, height)?,
+                    VideoFormat::Y4m => {
+                        let header = format!(
+                            "YUV4MPEG2 W{} H{} F{}:1 Ip A1:1 C444\n",
+                            width, height, self.fps
+                        );
+                        self.bytes.extend_from_slice(header.as_bytes());
+                    }
+                }
+                self.size = Some((width, height));
+            }
+            Some(size) if size != (width, height) => return Ok(()),
+            _ => {}
+        }
+        match self.format {
+            VideoFormat::Gif => {
+                let delay = (100.0 / self.fps as f32).round() as u16;
+                gif::write_frame(&mut self.bytes, width, height, rgba, delay.max(1))?;
+            }
+            VideoFormat::Y4m => {
+                self.bytes.extend_from_slice(b"FRAME\n");
+                write_yuv(&mut self.bytes, rgba);
+            }
+        }
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Complete the file and return its bytes.
+    pub fn finish(mut self) -> Vec<u8> {
+        if self.format == VideoFormat::Gif && self.size.is_some() {
+            gif::write_trailer(&mut self.bytes);
+        }
+        self.bytes
+    }
+}
+
+/// Append the Y, U and V planes of r, g, b, a pixels, with BT.601 studio swing.
+fn write_yuv(out: &mut Vec<u8>, rgba: &[u8]) {
+    let pixels = || {
+        rgba.chunks(4)
+            .map(|p| (p[0] as i32, p[1] as i32, p[2] as i32))
+    };
+    out.extend(pixels().map(|(r, g, b)| (((66 * r + 129 * g + 25 * b + 128) >> 8) + 16) as u8));
+    out.extend(pixels().map(|(r, g, b)| (((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128) as u8));
+    out.extend(pixels().map(|(r, g, b)| (((112 * r - 94 * g - 18 * b + 128) >> 8) + 128) as u8));
+}
+
+#[cfg(test)]
+#[test]
+fn record_a_y4m_stream() {
+    let mut recorder = Recorder::new(VideoFormat::Y4m, 2, 25);
+    assert!(!recorder.tick());
+    assert!(recorder.tick());
+    let white_and_black = [255, 255, 255, 255, 0, 0, 0, 255];
+    recorder.capture(2, 1, &white_and_black).unwrap();
+    recorder.capture(1, 1, &white_and_black[..4]).unwrap();
+    assert_eq!(recorder.frames, 1);
+    let bytes = recorder.finish();
+    let header = b"YUV4MPEG2 W2 H1 F25:1 Ip A1:1 C444\nFRAME\n";
+    assert_eq!(&bytes[..header.len()], &header[..]);
+    assert_eq!(&bytes[header.len()..], &[235, 16, 128, 128, 128, 128]);
+}
+
+#[test]
+fn record_a_gif_animation() {
+    let mut recorder = Recorder::new(VideoFormat::Gif, 1, 10);
+    let red = [255, 0, 0, 255];
+    let wide = vec![0; 70000 * 4];
+    assert!(recorder.capture(70000, 1, &wide).is_err());
+    recorder.capture(1, 1, &red).unwrap();
+    recorder.capture(1, 1, &red).unwrap();
+    assert_eq!(recorder.frames, 2);
+    let bytes = recorder.finish();
+    assert_eq!(&bytes[..6], b"GIF89a");
+    assert_eq!(&bytes[6..10], &[1, 0, 1, 0]);
+    assert!(bytes.len() > 2 * 256 * 3);
+    assert_eq!(bytes.last(), Some(&0x3b));
+}
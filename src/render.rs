@@ -0,0 +1,289 @@
+//! Overlays drawn on top of rendered frames.
+
+use color::Color;
+use grid::Grid;
+use std::f32::consts::PI;
+use wasm_bindgen::prelude::*;
+
+/// How sink cells are marked.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SinkStyle {
+    Hidden,
+    Tint,
+    Hatch,
+}
+
+/// How the potential is shown.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PotentialStyle {
+    Hidden,
+    Shading,
+    Isolines,
+}
+
+/// Picture exported as an image file.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Image {
+    /// The last rendered frame.
+    Frame,
+    /// Probability density |ψ|² alone.
+    Density,
+    PotentialLevel,
+    SinkMult,
+}
+
+/// Color of an overlay and its opacity in [0, 1].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Layer {
+    pub color: Color,
+    pub opacity: f32,
+}
+
+impl Layer {
+    pub fn new(color: Color, opacity: f32) -> Self {
+        Layer { color, opacity }
+    }
+
+    /// Blend the layer over a pixel, its opacity weighted by strength.
+    fn paint(&self, pixel: &mut Color, strength: f32) {
+        *pixel = pixel.blend(&self.color, self.opacity * strength);
+    }
+}
+
+/// Grids of the universe drawn over the field.
+pub struct Scene<'a> {
+    pub walls: &'a Grid<bool>,
+    pub barrier: &'a Grid<f32>,
+    pub sinks: &'a Grid<bool>,
+    pub potential: &'a Grid<f32>,
+}
+
+/// Overlay settings for the composited frame.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Composite {
+    /// Hard walls, and finite walls tinted by their relative height.
+    pub walls: Layer,
+    pub sinks: Layer,
+    pub sink_style: SinkStyle,
+    pub potential: Layer,
+    pub potential_style: PotentialStyle,
+    /// Potential difference between two isolines.
+    pub isoline_spacing: f32,
+    /// Lines along the top and left edges of every cell, when cells are larger than a pixel.
+    pub cell_grid: Option<Layer>,
+}
+
+impl Default for Composite {
+    fn default() -> Self {
+        Composite {
+            walls: Layer::new(Color::wall(), 1.0),
+            sinks: Layer::new(Color::new(0, 0, 0), 0.3),
+            sink_style: SinkStyle::Hidden,
+            potential: Layer::new(Color::white(), 0.5),
+            potential_style: PotentialStyle::Hidden,
+            isoline_spacing: 0.1,
+            cell_grid: None,
+        }
+    }
+}
+
+impl Composite {
+    /// Draw the overlays on a frame of the field colors at any resolution,
+    /// from the bottom: potential, sinks, walls and the cell grid.
+    /// Each pixel takes the overlays of the cell it falls in.
+    pub fn draw(&self, frame: &mut Grid<Color>, scene: &Scene) {
+        let (width, height) = (scene.walls.width, scene.walls.height);
+        let columns: Vec<usize> = (0..frame.width).map(|x| x * width / frame.width).collect();
+        let rows: Vec<usize> = (0..frame.height)
+            .map(|y| y * height / frame.height)
+            .collect();
+        let max_barrier = scene.barrier.max();
+        let max_potential = scene
+            .potential
+            .data
+            .iter()
+            .fold(0.0, |max: f32, v| max.max(v.abs()));
+        let band = |i: usize| (scene.potential.data[i] / self.isoline_spacing).floor();
+        let show_grid = frame.width >= 2 * width && frame.height >= 2 * height;
+        for y in 0..frame.height {
+            for x in 0..frame.width {
+                let (cx, cy) = (columns[x], rows[y]);
+                let i = cx + cy * width;
+                let right = x + 1 < frame.width && columns[x + 1] != cx;
+                let bottom = y + 1 < frame.height && rows[y + 1] != cy;
+                let pixel = &mut frame.data[x + y * frame.width];
+                match self.potential_style {
+                    PotentialStyle::Shading if max_potential > 0.0 => {
+                        let strength = scene.potential.data[i].abs() / max_potential;
+                        self.potential.paint(pixel, strength);
+                    }
+                    // a line runs along the right and bottom edges of cells at a band change
+                    PotentialStyle::Isolines
+                        if self.isoline_spacing > 0.0
+                            && ((right && band(i + 1) != band(i))
+                                || (bottom && band(i + width) != band(i))) =>
+                    {
+                        self.potential.paint(pixel, 1.0);
+                    }
+                    _ => {}
+                }
+                if scene.sinks.data[i] {
+                    match self.sink_style {
+                        SinkStyle::Tint => self.sinks.paint(pixel, 1.0),
+                        SinkStyle::Hatch if (x + y) % 4 == 0 => self.sinks.paint(pixel, 1.0),
+                        _ => {}
+                    }
+                }
+                if scene.walls.data[i] {
+                    self.walls.paint(pixel, 1.0);
+                } else if max_barrier > 0.0 && scene.barrier.data[i] > 0.0 {
+                    self.walls
+                        .paint(pixel, 0.6 * scene.barrier.data[i] / max_barrier);
+                }
+                if let Some(layer) = self.cell_grid {
+                    let left = x == 0 || columns[x - 1] != cx;
+                    let top = y == 0 || rows[y - 1] != cy;
+                    if show_grid && (left || top) {
+                        layer.paint(pixel, 1.0);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Draw one arrow per spacing cells showing the direction and relative strength of a vector field.
+/// The frame is expected to be the field grid upscaled by cell_size.
+pub fn draw_arrows(
+    frame: &mut Grid<Color>,
+    field: &Grid<(f32, f32)>,
+    cell_size: usize,
+    spacing: usize,
+    color: Color,
+) {
+    let max = field.max_length();
+    if max == 0.0 {
+        return;
+    }
+    let spacing = spacing.max(1);
+    let max_length = 0.9 * (spacing * cell_size) as f32;
+    for y in (spacing / 2..field.height).step_by(spacing) {
+        for x in (spacing / 2..field.width).step_by(spacing) {
+            let (vx, vy) = field.data[x + y * field.width];
+            let length = vx.hypot(vy) / max * max_length;
+            if length < 1.0 {
+                continue;
+            }
+            let angle = vy.atan2(vx);
+            let center = (
+                (x as f32 + 0.5) * cell_size as f32,
+                (y as f32 + 0.5) * cell_size as f32,
+            );
+            let tail = (
+                center.0 - 0.5 * length * angle.cos(),
+                center.1 - 0.5 * length * angle.sin(),
+            );
+            let head = (
+                center.0 + 0.5 * length * angle.cos(),
+                center.1 + 0.5 * length * angle.sin(),
+            );
+            frame.draw_line(tail, head, color);
+            let barb = 0.35 * length;
+            for side in [-1.0f32, 1.0].iter() {
+                let barb_angle = angle + side * 2.6;
+                let end = (
+                    head.0 + barb * barb_angle.cos(),
+                    head.1 + barb * barb_angle.sin(),
+                );
+                frame.draw_line(head, end, color);
+            }
+        }
+    }
+}
+
+/// Draw a ring of the given radius in pixels around each point given in cell units.
+pub fn mark_points(
+    frame: &mut Grid<Color>,
+    points: &[(f32, f32)],
+    cell_size: usize,
+    radius: f32,
+    color: Color,
+) {
+    let segments = 16;
+    for &(x, y) in points.iter() {
+        let center = (x * cell_size as f32, y * cell_size as f32);
+        for i in 0..segments {
+            let a0 = 2.0 * PI * i as f32 / segments as f32;
+            let a1 = 2.0 * PI * (i + 1) as f32 / segments as f32;
+            frame.draw_line(
+                (center.0 + radius * a0.cos(), center.1 + radius * a0.sin()),
+                (center.0 + radius * a1.cos(), center.1 + radius * a1.sin()),
+                color,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+use coord::Coord;
+
+#[cfg(test)]
+#[test]
+fn draw_arrows_along_the_field() {
+    let mut field = Grid::<(f32, f32)>::new(3, 3);
+    field.set(Coord::new(1, 1), (1.0, 0.0));
+    let mut frame = Grid::<Color>::new(3, 3).upscale(10);
+    draw_arrows(&mut frame, &field, 10, 1, Color::new(0, 0, 0));
+    assert_eq!(frame.get(Coord::new(15, 15)), Some(&Color::new(0, 0, 0)));
+    assert_eq!(frame.get(Coord::new(15, 5)), Some(&Color::white()));
+    assert_eq!(frame.get(Coord::new(5, 5)), Some(&Color::white()));
+}
+
+#[test]
+fn mark_points_with_rings() {
+    let mut frame = Grid::<Color>::new(4, 4).upscale(10);
+    mark_points(&mut frame, &[(2.0, 2.0)], 10, 5.0, Color::new(0, 0, 0));
+    assert_eq!(frame.get(Coord::new(25, 20)), Some(&Color::new(0, 0, 0)));
+    assert_eq!(frame.get(Coord::new(20, 20)), Some(&Color::white()));
+}
+
+#[test]
+fn composite_walls_sinks_and_isolines() {
+    let cells = Grid::<Color>::new(3, 2);
+    let mut walls = Grid::<bool>::new(3, 2);
+    walls.set(Coord::new(2, 1), true);
+    let mut sinks = Grid::<bool>::new(3, 2);
+    sinks.set(Coord::new(0, 1), true);
+    let mut potential = Grid::<f32>::new(3, 2);
+    potential.set(Coord::new(1, 0), 0.25);
+    let barrier = Grid::<f32>::new(3, 2);
+    let scene = Scene {
+        walls: &walls,
+        barrier: &barrier,
+        sinks: &sinks,
+        potential: &potential,
+    };
+    let black = Color::new(0, 0, 0);
+    let composite = Composite {
+        sinks: Layer::new(black, 1.0),
+        sink_style: SinkStyle::Hatch,
+        potential: Layer::new(black, 1.0),
+        potential_style: PotentialStyle::Isolines,
+        cell_grid: Some(Layer::new(black, 0.5)),
+        ..Composite::default()
+    };
+    let mut frame = cells.upscale(4);
+    composite.draw(&mut frame, &scene);
+    assert_eq!(frame.get(Coord::new(10, 6)), Some(&Color::wall()));
+    assert_eq!(frame.get(Coord::new(3, 2)), Some(&black));
+    assert_eq!(frame.get(Coord::new(6, 2)), Some(&Color::white()));
+    assert_eq!(frame.get(Coord::new(1, 7)), Some(&black));
+    assert_eq!(frame.get(Coord::new(2, 7)), Some(&Color::white()));
+    assert_eq!(
+        frame.get(Coord::new(4, 6)),
+        Some(&Color::new(128, 128, 128))
+    );
+}
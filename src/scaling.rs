@@ -0,0 +1,128 @@
+//! Mapping of amplitudes |ψ| to brightness levels in [0, 1] before coloring.
+
+use complex::Complex;
+use grid::Grid;
+
+/// Shape of the mapping from amplitude to level.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    /// Level proportional to the amplitude.
+    Linear,
+    /// Level rising linearly with log10 |ψ| over the given number of decades below the maximum.
+    Log { decades: f32 },
+    /// Level of the relative amplitude raised to the power gamma.
+    Gamma { gamma: f32 },
+    /// Linear, the maximum following a running percentile of the nonzero amplitudes.
+    Auto { percentile: f32, smoothing: f32 },
+}
+
+/// Amplitude scaling shared by all renderers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Scaling {
+    pub mode: Mode,
+    /// Amplitude reaching the full level.
+    pub max: f32,
+}
+
+impl Default for Scaling {
+    fn default() -> Self {
+        Scaling::new(Mode::Linear, 2.0)
+    }
+}
+
+impl Scaling {
+    pub fn new(mode: Mode, max: f32) -> Self {
+        Scaling { mode, max }
+    }
+
+    /// Same mode with another maximum, for renderers normalizing their own field.
+    pub fn with_max(&self, max: f32) -> Self {
+        Scaling::new(self.mode, max)
+    }
+
+    /// Level in [0, 1] of an amplitude.
+    pub fn level(&self, amplitude: f32) -> f32 {
+        if amplitude <= 0.0 || self.max <= 0.0 {
+            return 0.0;
+        }
+        let relative = amplitude / self.max;
+        let level = match self.mode {
+            Mode::Linear | Mode::Auto { .. } => relative,
+            Mode::Log { decades } => 1.0 + relative.log10() / decades,
+            Mode::Gamma { gamma } => relative.powf(gamma),
+        };
+        level.clamp(0.0, 1.0)
+    }
+
+    /// Complex value with the same phase and the level of the amplitude as radius.
+    pub fn apply(&self, value: Complex) -> Complex {
+        Complex::from_polar(self.level(value.radius()), value.arg())
+    }
+
+    /// Signed level of a real value.
+    pub fn signed(&self, value: f32) -> f32 {
+        self.level(value.abs()).copysign(value)
+    }
+
+    /// Move the maximum of the auto exposure toward the percentile of the field amplitudes.
+    /// Other modes and null fields are left untouched.
+    pub fn expose(&mut self, field: &Grid<Complex>) {
+        if let Mode::Auto {
+            percentile,
+            smoothing,
+        } = self.mode
+        {
+            let mut amplitudes: Vec<f32> = field
+                .data
+                .iter()
+                .map(|c| c.radius())
+                .filter(|&r| r > 0.0)
+                .collect();
+            if amplitudes.is_empty() {
+                return;
+            }
+            let rank = ((amplitudes.len() - 1) as f32 * percentile.clamp(0.0, 1.0)).round();
+            let (_, &mut target, _) =
+                amplitudes.select_nth_unstable_by(rank as usize, |a, b| a.partial_cmp(b).unwrap());
+            self.max += smoothing.clamp(0.0, 1.0) * (target - self.max);
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn levels_of_each_mode() {
+    let linear = Scaling::new(Mode::Linear, 2.0);
+    assert_eq!(linear.level(1.0), 0.5);
+    assert_eq!(linear.level(3.0), 1.0);
+    let log = Scaling::new(Mode::Log { decades: 4.0 }, 1.0);
+    assert!((log.level(0.01) - 0.5).abs() < 1e-6);
+    assert_eq!(log.level(1e-6), 0.0);
+    let gamma = Scaling::new(Mode::Gamma { gamma: 0.5 }, 1.0);
+    assert!((gamma.level(0.25) - 0.5).abs() < 1e-6);
+    assert_eq!(gamma.signed(-0.25), -0.5);
+    let phase = linear.apply(Complex::from_polar(1.0, 2.0)).arg();
+    assert!((phase - 2.0).abs() < 1e-6);
+}
+
+#[test]
+fn auto_exposure_follows_the_percentile() {
+    let mut field = Grid::<Complex>::new(10, 10);
+    for (i, cell) in field.data.iter_mut().take(50).enumerate() {
+        *cell = Complex::new(i as f32 + 1.0, 0.0);
+    }
+    let mut scaling = Scaling::new(
+        Mode::Auto {
+            percentile: 0.9,
+            smoothing: 0.5,
+        },
+        1.0,
+    );
+    scaling.expose(&field);
+    assert_eq!(scaling.max, 23.0);
+    for _ in 0..20 {
+        scaling.expose(&field);
+    }
+    assert!((scaling.max - 45.0).abs() < 1e-3);
+    assert_eq!(scaling.level(45.0), 1.0);
+}
@@ -0,0 +1,165 @@
+//! Transmission and reflection through a barrier, measured by splitting the field
+//! into incident, barrier and transmitted zones along the direction of propagation.
+
+extern crate wasm_bindgen;
+
+use complex::Complex;
+use grid::Grid;
+use wasm_bindgen::prelude::*;
+
+/// Probability in each zone, recorded once per step.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Zones {
+    pub incident: f32,
+    pub barrier: f32,
+    pub transmitted: f32,
+}
+
+/// Zones split at two positions along the x axis, or the y axis when vertical.
+/// Packets are expected to come from the low coordinates.
+#[derive(Clone, Debug)]
+pub struct Scattering {
+    barrier_start: f32,
+    barrier_end: f32,
+    vertical: bool,
+    pub history: Vec<Zones>,
+}
+
+impl Zones {
+    /// Fraction of the remaining probability past the barrier.
+    pub fn transmission(&self) -> f32 {
+        let total = self.incident + self.barrier + self.transmitted;
+        if total > 0.0 {
+            self.transmitted / total
+        } else {
+            0.0
+        }
+    }
+
+    /// Fraction of the remaining probability before the barrier.
+    pub fn reflection(&self) -> f32 {
+        let total = self.incident + self.barrier + self.transmitted;
+        if total > 0.0 {
+            self.incident / total
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Scattering {
+    pub fn new(barrier_start: f32, barrier_end: f32, vertical: bool) -> Self {
+        Scattering {
+            barrier_start: barrier_start.min(barrier_end),
+            barrier_end: barrier_start.max(barrier_end),
+            vertical,
+            history: Vec::new(),
+        }
+    }
+
+    /// Integrate the probability in each zone of the field.
+    pub fn measure(&self, quantum: &Grid<Complex>) -> Zones {
+        let mut zones = Zones::default();
+        for (i, c) in quantum.data.iter().enumerate() {
+            let position = if self.vertical {
+                (i / quantum.width) as f32
+            } else {
+                (i % quantum.width) as f32
+            };
+            if position < self.barrier_start {
+                zones.incident += c.norm();
+            } else if position > self.barrier_end {
+                zones.transmitted += c.norm();
+            } else {
+                zones.barrier += c.norm();
+            }
+        }
+        zones
+    }
+
+    /// Append the zone probabilities of the field to the history.
+    pub fn record(&mut self, quantum: &Grid<Complex>) {
+        let zones = self.measure(quantum);
+        self.history.push(zones);
+    }
+
+    /// True once the barrier holds less than tolerance of the probability and both coefficients
+    /// moved by less than tolerance, relative to their value, over the last window records.
+    pub fn is_settled(&self, window: usize, tolerance: f32) -> bool {
+        if window == 0 || self.history.len() < window {
+            return false;
+        }
+        let recent = &self.history[self.history.len() - window..];
+        let steady = |coefficient: &dyn Fn(&Zones) -> f32| {
+            let (min, max) = recent
+                .iter()
+                .fold((f32::INFINITY, 0.0f32), |(min, max), z| {
+                    (min.min(coefficient(z)), max.max(coefficient(z)))
+                });
+            max > 0.0 && max - min <= tolerance * max
+        };
+        let last = recent[window - 1];
+        let total = last.incident + last.barrier + last.transmitted;
+        last.barrier <= tolerance * total
+            && steady(&|z: &Zones| z.transmission())
+            && steady(&|z: &Zones| z.reflection())
+    }
+}
+
+/// Transmission coefficient of a 1D rectangular barrier of the given height and width
+/// for a particle of the given energy, in units where ħ = m = 1.
+#[wasm_bindgen]
+pub fn analytic_transmission(energy: f32, height: f32, width: f32) -> f32 {
+    if energy <= 0.0 {
+        return 0.0;
+    }
+    if height == 0.0 {
+        return 1.0;
+    }
+    let difference = height - energy;
+    let ratio = if difference > 0.0 {
+        let kappa = (2.0 * difference).sqrt();
+        height * height * (kappa * width).sinh().powi(2) / (4.0 * energy * difference)
+    } else if difference < 0.0 {
+        let k = (-2.0 * difference).sqrt();
+        height * height * (k * width).sin().powi(2) / (4.0 * energy * -difference)
+    } else {
+        energy * width * width / 2.0
+    };
+    1.0 / (1.0 + ratio)
+}
+
+#[cfg(test)]
+#[test]
+fn analytic_transmission_limits() {
+    assert_eq!(analytic_transmission(0.5, 0.0, 3.0), 1.0);
+    assert_eq!(analytic_transmission(0.0, 1.0, 3.0), 0.0);
+    // tunnelling decays with width, resonances above the barrier are transparent
+    assert!(analytic_transmission(0.5, 1.0, 4.0) < analytic_transmission(0.5, 1.0, 2.0));
+    let k = (2.0f32 * (1.5 - 1.0)).sqrt();
+    assert!((analytic_transmission(1.5, 1.0, std::f32::consts::PI / k) - 1.0).abs() < 1e-5);
+    // continuous across E = V
+    let below = analytic_transmission(0.999, 1.0, 2.0);
+    let at = analytic_transmission(1.0, 1.0, 2.0);
+    let above = analytic_transmission(1.001, 1.0, 2.0);
+    assert!((below - at).abs() < 1e-2 && (above - at).abs() < 1e-2);
+}
+
+#[test]
+fn split_probability_into_zones() {
+    let mut grid = Grid::<Complex>::new(6, 1);
+    for (i, cell) in grid.data.iter_mut().enumerate() {
+        *cell = Complex::new(if i == 4 { 2.0 } else { 1.0 }, 0.0);
+    }
+    let scattering = Scattering::new(3.0, 2.0, false);
+    let zones = scattering.measure(&grid);
+    assert_eq!(
+        zones,
+        Zones {
+            incident: 2.0,
+            barrier: 2.0,
+            transmitted: 5.0
+        }
+    );
+    assert_eq!(zones.transmission(), 5.0 / 9.0);
+}
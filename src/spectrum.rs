@@ -0,0 +1,100 @@
+//! Energy spectrum from the autocorrelation C(t) = ⟨ψ(0)|ψ(t)⟩.
+//! With ψ(0) = Σ cₙ φₙ the autocorrelation is Σ |cₙ|² e^{-iEₙt},
+//! so its Fourier transform peaks at the eigenvalues Eₙ.
+
+use complex::Complex;
+use grid::Grid;
+use std::f32::consts::PI;
+
+/// Peaks smaller than this fraction of the largest one are ignored.
+const PEAK_THRESHOLD: f32 = 0.02;
+
+/// Autocorrelation samples recorded once per step.
+#[derive(Clone, Debug)]
+pub struct Autocorrelation {
+    initial: Grid<Complex>,
+    dt: f32,
+    pub samples: Vec<Complex>,
+}
+
+impl Autocorrelation {
+    /// Start recording from the given initial state, sampled every dt.
+    pub fn new(initial: &Grid<Complex>, dt: f32) -> Self {
+        let mut autocorrelation = Autocorrelation {
+            initial: initial.clone(),
+            dt,
+            samples: Vec::new(),
+        };
+        autocorrelation.record(initial);
+        autocorrelation
+    }
+
+    /// Append the overlap of the current state with the initial state.
+    pub fn record(&mut self, current: &Grid<Complex>) {
+        let overlap = self
+            .initial
+            .data
+            .iter()
+            .zip(current.data.iter())
+            .fold(Complex::zero(), |sum, (a, b)| sum.add(&a.conj().mul(b)));
+        self.samples.push(overlap);
+    }
+
+    /// Spectral weight at evenly spaced energies from e_min to e_max, Hann windowed.
+    pub fn spectrum(&self, e_min: f32, e_max: f32, bins: usize) -> Vec<f32> {
+        let n = self.samples.len();
+        (0..bins)
+            .map(|bin| {
+                let energy = e_min + (e_max - e_min) * bin as f32 / (bins.max(2) - 1) as f32;
+                let sum = self
+                    .samples
+                    .iter()
+                    .enumerate()
+                    .fold(Complex::zero(), |sum, (i, c)| {
+                        let window = 0.5 + 0.5 * (PI * i as f32 / n as f32).cos();
+                        let phase = Complex::from_polar(window, energy * i as f32 * self.dt);
+                        sum.add(&c.mul(&phase))
+                    });
+                sum.radius() * self.dt
+            })
+            .collect()
+    }
+}
+
+/// Energies of the local maxima of a spectrum sampled from e_min to e_max,
+/// refined by fitting a parabola through each peak and its neighbours.
+pub fn find_peaks(spectrum: &[f32], e_min: f32, e_max: f32) -> Vec<f32> {
+    let max = spectrum.iter().cloned().fold(0.0, f32::max);
+    let step = (e_max - e_min) / (spectrum.len().max(2) - 1) as f32;
+    spectrum
+        .windows(3)
+        .enumerate()
+        .filter(|(_, w)| w[1] > w[0] && w[1] >= w[2] && w[1] > PEAK_THRESHOLD * max)
+        .map(|(i, w)| {
+            let curvature = w[0] - 2.0 * w[1] + w[2];
+            let offset = if curvature != 0.0 {
+                0.5 * (w[0] - w[2]) / curvature
+            } else {
+                0.0
+            };
+            e_min + (i as f32 + 1.0 + offset) * step
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[test]
+fn spectrum_of_two_levels() {
+    let mut autocorrelation = Autocorrelation::new(&Grid::<Complex>::new(1, 1), 0.1);
+    autocorrelation.samples = (0..2000)
+        .map(|i| {
+            let t = i as f32 * 0.1;
+            Complex::from_polar(0.7, -0.5 * t).add(&Complex::from_polar(0.3, -1.2 * t))
+        })
+        .collect();
+    let spectrum = autocorrelation.spectrum(0.0, 2.0, 401);
+    let peaks = find_peaks(&spectrum, 0.0, 2.0);
+    assert_eq!(peaks.len(), 2);
+    assert!((peaks[0] - 0.5).abs() < 0.005);
+    assert!((peaks[1] - 1.2).abs() < 0.005);
+}
@@ -0,0 +1,130 @@
+//! SVG figures: a raster image under vector lines, in cell units.
+
+use contour::Polyline;
+use png;
+use render::Layer;
+use std::fmt::Write;
+use std::io;
+
+const BASE64: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Document covering width by height cells, shown with cell_size pixels per cell.
+pub struct Svg {
+    width: usize,
+    height: usize,
+    cell_size: f32,
+    body: String,
+}
+
+impl Svg {
+    pub fn new(width: usize, height: usize, cell_size: f32) -> Self {
+        Svg {
+            width,
+            height,
+            cell_size,
+            body: String::new(),
+        }
+    }
+
+    /// Embed r, g, b, a pixels as a PNG stretched over the whole figure, kept blocky.
+    /// An empty image cannot be encoded and leaves the figure unchanged.
+    pub fn image(&mut self, width: usize, height: usize, rgba: &[u8]) -> io::Result<()> {
+        let mut bytes = Vec::new();
+        png::write(&mut bytes, width, height, rgba)?;
+        writeln!(
+            self.body,
+            "<image width=\"{}\" height=\"{}\" preserveAspectRatio=\"none\" \
+             style=\"image-rendering:pixelated\" xlink:href=\"data:image/png;base64,{}\"/>",
+            self.width,
+            self.height,
+            base64(&bytes)
+        )
+        .unwrap();
+        Ok(())
+    }
+
+    /// Stroke lines in a group, line_width in cells.
+    pub fn polylines(&mut self, lines: &[Polyline], layer: Layer, line_width: f32) {
+        if lines.is_empty() {
+            return;
+        }
+        let color = layer.color;
+        writeln!(
+            self.body,
+            "<g fill=\"none\" stroke=\"#{:02x}{:02x}{:02x}\" stroke-opacity=\"{}\" \
+             stroke-width=\"{}\" stroke-linejoin=\"round\" stroke-linecap=\"round\">",
+            color.r, color.g, color.b, layer.opacity, line_width
+        )
+        .unwrap();
+        for line in lines {
+            self.body.push_str("<polyline points=\"");
+            for (i, &(x, y)) in line.iter().enumerate() {
+                let separator = if i == 0 { "" } else { " " };
+                write!(self.body, "{}{:.3},{:.3}", separator, x, y).unwrap();
+            }
+            self.body.push_str("\"/>\n");
+        }
+        self.body.push_str("</g>\n");
+    }
+
+    /// Complete the document.
+    pub fn finish(self) -> String {
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\" \
+             width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n{}</svg>\n",
+            self.width as f32 * self.cell_size,
+            self.height as f32 * self.cell_size,
+            self.width,
+            self.height,
+            self.body
+        )
+    }
+}
+
+/// Standard base64 with padding.
+fn base64(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = (chunk[0] as u32) << 16
+            | (*chunk.get(1).unwrap_or(&0) as u32) << 8
+            | *chunk.get(2).unwrap_or(&0) as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                text.push(BASE64[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+#[test]
+fn base64_of_known_strings() {
+    assert_eq!(base64(b""), "");
+    assert_eq!(base64(b"f"), "Zg==");
+    assert_eq!(base64(b"fo"), "Zm8=");
+    assert_eq!(base64(b"foo"), "Zm9v");
+    assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+}
+
+#[test]
+fn write_a_figure() {
+    use color::Color;
+
+    let mut svg = Svg::new(4, 2, 10.0);
+    svg.image(1, 1, &[255, 0, 0, 255]).unwrap();
+    assert!(svg.image(0, 0, &[]).is_err());
+    let layer = Layer::new(Color::new(255, 128, 0), 0.5);
+    svg.polylines(&[vec![(0.5, 0.5), (3.5, 1.25)]], layer, 0.1);
+    svg.polylines(&[], layer, 0.1);
+    let text = svg.finish();
+    assert!(text.starts_with("<svg "));
+    assert!(text.contains("width=\"40\" height=\"20\" viewBox=\"0 0 4 2\""));
+    assert!(text.contains("xlink:href=\"data:image/png;base64,iVBORw0KGgo"));
+    assert!(text.contains("stroke=\"#ff8000\" stroke-opacity=\"0.5\""));
+    assert!(text.contains("points=\"0.500,0.500 3.500,1.250\""));
+    assert_eq!(text.matches("<g ").count(), 1);
+    assert!(text.ends_with("</svg>\n"));
+}
@@ -0,0 +1,216 @@
+//! Text rendering of frames for terminals, two cells per character with upper half blocks.
+
+use color::Color;
+use grid::Grid;
+use std::env;
+use std::fmt::Write;
+
+/// Brightness ramp used when colors are disabled, from empty to full.
+const RAMP: &[u8] = b" .:-=+*#%@";
+
+/// Levels of each channel in the 6 x 6 x 6 color cube of 256 color terminals.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Default xterm values of the 16 basic colors.
+const BASIC_COLORS: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// Colors a terminal can show.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Palette {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+    /// No escape codes at all, brightness drawn with characters.
+    Monochrome,
+}
+
+impl Palette {
+    /// Guess the palette from the NO_COLOR, COLORTERM and TERM variables.
+    pub fn from_env() -> Palette {
+        let var = |name: &str| env::var(name).ok();
+        Palette::detect(var("NO_COLOR"), var("COLORTERM"), var("TERM"))
+    }
+
+    /// A non empty NO_COLOR disables colors, following https://no-color.org.
+    pub fn detect(
+        no_color: Option<String>,
+        colorterm: Option<String>,
+        term: Option<String>,
+    ) -> Palette {
+        if no_color.is_some_and(|value| !value.is_empty()) {
+            Palette::Monochrome
+        } else if colorterm.is_some_and(|value| value == "truecolor" || value == "24bit") {
+            Palette::TrueColor
+        } else if term.as_ref().is_some_and(|value| value.contains("256")) {
+            Palette::Ansi256
+        } else if term.is_some_and(|value| value == "dumb") {
+            Palette::Monochrome
+        } else {
+            Palette::Ansi16
+        }
+    }
+
+    /// Escape sequence setting the foreground, or the background, to the nearest color.
+    fn escape(self, color: Color, background: bool) -> String {
+        match self {
+            Palette::TrueColor => {
+                let layer = if background { 48 } else { 38 };
+                format!("\x1b[{};2;{};{};{}m", layer, color.r, color.g, color.b)
+            }
+            Palette::Ansi256 => {
+                let layer = if background { 48 } else { 38 };
+                format!("\x1b[{};5;{}m", layer, ansi256(color))
+            }
+            Palette::Ansi16 => {
+                let index = nearest(&BASIC_COLORS, color);
+                let base = match (background, index < 8) {
+                    (false, true) => 30,
+                    (false, false) => 90 - 8,
+                    (true, true) => 40,
+                    (true, false) => 100 - 8,
+                };
+                format!("\x1b[{}m", base + index)
+            }
+            Palette::Monochrome => String::new(),
+        }
+    }
+}
+
+/// Lines of upper half blocks showing two rows of colors each, the top one in the foreground.
+/// An odd last row is drawn over black. Every line resets the colors before its end.
+pub fn half_blocks(colors: &Grid<Color>, palette: Palette) -> String {
+    let mut text = String::new();
+    let black = Color::new(0, 0, 0);
+    for row in (0..colors.height).step_by(2) {
+        let mut current: Option<(Color, Color)> = None;
+        for x in 0..colors.width {
+            let top = colors.data[x + row * colors.width];
+            let bottom = if row + 1 < colors.height {
+                colors.data[x + (row + 1) * colors.width]
+            } else {
+                black
+            };
+            if palette == Palette::Monochrome {
+                let level = (luminance(top) + luminance(bottom)) / 2.0;
+                let index = (level * (RAMP.len() - 1) as f32).round() as usize;
+                text.push(RAMP[index] as char);
+                continue;
+            }
+            if current != Some((top, bottom)) {
+                text.push_str(&palette.escape(top, false));
+                text.push_str(&palette.escape(bottom, true));
+                current = Some((top, bottom));
+            }
+            text.push('▀');
+        }
+        if palette != Palette::Monochrome {
+            text.push_str("\x1b[0m");
+        }
+        writeln!(text).unwrap();
+    }
+    text
+}
+
+/// Relative luminance in [0, 1], without gamma correction.
+fn luminance(color: Color) -> f32 {
+    (0.2126 * color.r as f32 + 0.7152 * color.g as f32 + 0.0722 * color.b as f32) / 255.0
+}
+
+/// Index of the closest color of the cube or of the gray ramp of 256 color terminals.
+fn ansi256(color: Color) -> usize {
+    let level = |c: u8| {
+        CUBE_LEVELS
+            .iter()
+            .enumerate()
+            .min_by_key(|&(_, &l)| (l as i32 - c as i32).abs())
+            .unwrap()
+            .0
+    };
+    let (r, g, b) = (level(color.r), level(color.g), level(color.b));
+    let cube = (CUBE_LEVELS[r], CUBE_LEVELS[g], CUBE_LEVELS[b]);
+    let average = (color.r as usize + color.g as usize + color.b as usize) / 3;
+    let step = (average.saturating_sub(3) / 10).min(23);
+    let gray = (8 + 10 * step) as u8;
+    if distance((gray, gray, gray), color) < distance(cube, color) {
+        232 + step
+    } else {
+        16 + 36 * r + 6 * g + b
+    }
+}
+
+fn nearest(colors: &[(u8, u8, u8)], color: Color) -> usize {
+    (0..colors.len())
+        .min_by_key(|&i| distance(colors[i], color))
+        .unwrap()
+}
+
+fn distance(a: (u8, u8, u8), b: Color) -> i32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2);
+    d(a.0, b.r) + d(a.1, b.g) + d(a.2, b.b)
+}
+
+#[cfg(test)]
+#[test]
+fn detect_the_palette() {
+    let some = |s: &str| Some(s.to_string());
+    let detect = Palette::detect;
+    assert_eq!(
+        detect(some("1"), some("truecolor"), None),
+        Palette::Monochrome
+    );
+    assert_eq!(
+        detect(some(""), some("truecolor"), None),
+        Palette::TrueColor
+    );
+    assert_eq!(detect(None, None, some("xterm-256color")), Palette::Ansi256);
+    assert_eq!(detect(None, None, some("xterm")), Palette::Ansi16);
+    assert_eq!(detect(None, None, some("dumb")), Palette::Monochrome);
+}
+
+#[test]
+fn nearest_terminal_colors() {
+    assert_eq!(ansi256(Color::new(255, 0, 0)), 196);
+    assert_eq!(ansi256(Color::new(128, 128, 128)), 244);
+    assert_eq!(ansi256(Color::white()), 231);
+    assert_eq!(nearest(&BASIC_COLORS, Color::new(250, 10, 10)), 9);
+}
+
+#[test]
+fn draw_two_rows_per_line() {
+    let mut colors = Grid::<Color>::new(2, 3);
+    colors.data[0] = Color::new(255, 0, 0);
+    let text = half_blocks(&colors, Palette::TrueColor);
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("\x1b[38;2;255;0;0m\x1b[48;2;255;255;255m▀"));
+    assert_eq!(lines[1].matches('▀').count(), 2);
+    assert!(lines[1].contains("\x1b[48;2;0;0;0m"));
+    assert_eq!(
+        half_blocks(&colors, Palette::Ansi16)
+            .lines()
+            .next()
+            .unwrap()
+            .matches("\x1b[91m")
+            .count(),
+        1
+    );
+    let white = Grid::<Color>::new(2, 2);
+    assert_eq!(half_blocks(&white, Palette::Monochrome), "@@\n");
+}
@@ -0,0 +1,153 @@
+//! Phase singularities of the quantum field and their trajectories.
+
+use complex::Complex;
+use grid::Grid;
+use std::f32::consts::PI;
+
+/// Phase singularity at the center of a plaquette of four cells.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Vortex {
+    pub x: f32,
+    pub y: f32,
+    pub winding: i32,
+}
+
+/// Path followed by one vortex over successive steps.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trajectory {
+    pub winding: i32,
+    pub points: Vec<(f32, f32)>,
+    pub alive: bool,
+}
+
+/// Links the vortices found at each step into trajectories.
+#[derive(Clone, Debug, Default)]
+pub struct VortexTracker {
+    pub max_distance: f32,
+    pub trajectories: Vec<Trajectory>,
+}
+
+/// Wrap a phase difference into [-π, π).
+fn wrap(angle: f32) -> f32 {
+    (angle + PI).rem_euclid(2.0 * PI) - PI
+}
+
+/// Find vortices by summing the wrapped phase differences around each plaquette.
+/// Plaquettes with a cell of amplitude below threshold are skipped as phase noise.
+pub fn find_vortices(quantum: &Grid<Complex>, threshold: f32) -> Vec<Vortex> {
+    let mut vortices = Vec::new();
+    let width = quantum.width;
+    for y in 0..quantum.height.saturating_sub(1) {
+        for x in 0..width.saturating_sub(1) {
+            let corners = [
+                quantum.data[x + y * width],
+                quantum.data[x + 1 + y * width],
+                quantum.data[x + 1 + (y + 1) * width],
+                quantum.data[x + (y + 1) * width],
+            ];
+            if corners.iter().any(|c| c.radius() <= threshold) {
+                continue;
+            }
+            let circulation: f32 = (0..4)
+                .map(|i| wrap(corners[(i + 1) % 4].arg() - corners[i].arg()))
+                .sum();
+            let winding = (circulation / (2.0 * PI)).round() as i32;
+            if winding != 0 {
+                vortices.push(Vortex {
+                    x: x as f32 + 0.5,
+                    y: y as f32 + 0.5,
+                    winding,
+                });
+            }
+        }
+    }
+    vortices
+}
+
+impl VortexTracker {
+    pub fn new(max_distance: f32) -> Self {
+        VortexTracker {
+            max_distance,
+            trajectories: Vec::new(),
+        }
+    }
+
+    /// Extend each live trajectory with the closest vortex of the same winding within
+    /// max_distance; unmatched trajectories end and unmatched vortices start new ones.
+    pub fn update(&mut self, vortices: &[Vortex]) {
+        let mut taken = vec![false; vortices.len()];
+        let max_distance = self.max_distance;
+        for trajectory in self.trajectories.iter_mut().filter(|t| t.alive) {
+            let &(tx, ty) = trajectory.points.last().unwrap();
+            let closest = vortices
+                .iter()
+                .enumerate()
+                .filter(|&(i, v)| !taken[i] && v.winding == trajectory.winding)
+                .map(|(i, v)| (i, (v.x - tx).hypot(v.y - ty)))
+                .filter(|&(_, d)| d <= max_distance)
+                .fold(None, |best: Option<(usize, f32)>, (i, d)| match best {
+                    Some((_, best_d)) if best_d <= d => best,
+                    _ => Some((i, d)),
+                });
+            match closest {
+                Some((i, _)) => {
+                    taken[i] = true;
+                    trajectory.points.push((vortices[i].x, vortices[i].y));
+                }
+                None => trajectory.alive = false,
+            }
+        }
+        for (vortex, _) in vortices.iter().zip(taken.iter()).filter(|(_, &t)| !t) {
+            self.trajectories.push(Trajectory {
+                winding: vortex.winding,
+                points: vec![(vortex.x, vortex.y)],
+                alive: true,
+            });
+        }
+    }
+
+    /// Forget all trajectories.
+    pub fn clear(&mut self) {
+        self.trajectories.clear();
+    }
+}
+
+#[cfg(test)]
+fn vortex_field(cx: f32, cy: f32, winding: i32) -> Grid<Complex> {
+    let mut grid = Grid::<Complex>::new(10, 10);
+    for (i, cell) in grid.data.iter_mut().enumerate() {
+        let (dx, dy) = ((i % 10) as f32 - cx, (i / 10) as f32 - cy);
+        *cell = Complex::from_polar(1.0, winding as f32 * dy.atan2(dx));
+    }
+    grid
+}
+
+#[cfg(test)]
+#[test]
+fn find_single_vortices() {
+    let vortices = find_vortices(&vortex_field(4.5, 5.5, 1), 0.0);
+    assert_eq!(
+        vortices,
+        vec![Vortex {
+            x: 4.5,
+            y: 5.5,
+            winding: 1
+        }]
+    );
+    let antivortices = find_vortices(&vortex_field(2.5, 2.5, -1), 0.0);
+    assert_eq!(antivortices.len(), 1);
+    assert_eq!(antivortices[0].winding, -1);
+    assert!(find_vortices(&Grid::<Complex>::new(4, 4), 0.0).is_empty());
+}
+
+#[test]
+fn track_a_moving_vortex() {
+    let mut tracker = VortexTracker::new(1.5);
+    tracker.update(&find_vortices(&vortex_field(3.5, 3.5, 1), 0.0));
+    tracker.update(&find_vortices(&vortex_field(4.5, 3.5, 1), 0.0));
+    tracker.update(&find_vortices(&vortex_field(8.5, 3.5, 1), 0.0));
+    assert_eq!(tracker.trajectories.len(), 2);
+    assert_eq!(tracker.trajectories[0].points, vec![(3.5, 3.5), (4.5, 3.5)]);
+    assert!(!tracker.trajectories[0].alive);
+    assert!(tracker.trajectories[1].alive);
+}
@@ -0,0 +1,92 @@
+//! Wigner quasi-probability distribution of the field sampled along a straight cut.
+
+use complex::Complex;
+use coord::Coord;
+use grid::Grid;
+use std::f32::consts::PI;
+
+/// Sample the field at evenly spaced points from one end of the cut to the other,
+/// interpolating bilinearly between cells.
+pub fn sample_line(
+    quantum: &Grid<Complex>,
+    from: (f32, f32),
+    to: (f32, f32),
+    samples: usize,
+) -> Vec<Complex> {
+    let at = |x: i32, y: i32| *quantum.get(Coord::new(x, y)).unwrap_or(&Complex::zero());
+    (0..samples)
+        .map(|i| {
+            let t = if samples > 1 {
+                i as f32 / (samples - 1) as f32
+            } else {
+                0.0
+            };
+            let x = from.0 + (to.0 - from.0) * t;
+            let y = from.1 + (to.1 - from.1) * t;
+            let (x0, y0) = (x.floor(), y.floor());
+            let (fx, fy) = (x - x0, y - y0);
+            let (x0, y0) = (x0 as i32, y0 as i32);
+            let top = at(x0, y0).scale(1.0 - fx).add(&at(x0 + 1, y0).scale(fx));
+            let bottom = at(x0, y0 + 1)
+                .scale(1.0 - fx)
+                .add(&at(x0 + 1, y0 + 1).scale(fx));
+            top.scale(1.0 - fy).add(&bottom.scale(fy))
+        })
+        .collect()
+}
+
+/// Discrete Wigner function W(x, p) = 1/π Σ ψ*(x + y) ψ(x - y) e^{2ipy}.
+/// Columns are the samples, rows the momenta from +π/2 at the top down to -π/2.
+pub fn wigner(psi: &[Complex]) -> Grid<f32> {
+    let n = psi.len();
+    let mut w = Grid::<f32>::new(n, n);
+    for x in 0..n {
+        let reach = x.min(n - 1 - x) as i32;
+        for row in 0..n {
+            let p = PI / 2.0 - PI * (row as f32 + 0.5) / n as f32;
+            let mut sum = 0.0;
+            for y in -reach..=reach {
+                let product = psi[(x as i32 + y) as usize]
+                    .conj()
+                    .mul(&psi[(x as i32 - y) as usize]);
+                sum += product
+                    .mul(&Complex::from_polar(1.0, 2.0 * p * y as f32))
+                    .re;
+            }
+            w.data[x + row * n] = sum / PI;
+        }
+    }
+    w
+}
+
+/// Largest difference between the position marginal ∫W dp and |ψ(x)|².
+pub fn marginal_error(w: &Grid<f32>, psi: &[Complex]) -> f32 {
+    let dp = PI / w.height as f32;
+    psi.iter()
+        .enumerate()
+        .map(|(x, c)| {
+            let marginal: f32 = (0..w.height).map(|row| w.data[x + row * w.width]).sum();
+            (marginal * dp - c.norm()).abs()
+        })
+        .fold(0.0, f32::max)
+}
+
+#[cfg(test)]
+#[test]
+fn sample_line_interpolates() {
+    let mut grid = Grid::<Complex>::new(3, 3);
+    grid.set(Coord::new(1, 1), Complex::new(1.0, 0.0));
+    let samples = sample_line(&grid, (0.0, 1.0), (2.0, 1.0), 5);
+    let re: Vec<f32> = samples.iter().map(|c| c.re).collect();
+    assert_eq!(re, vec![0.0, 0.5, 1.0, 0.5, 0.0]);
+}
+
+#[test]
+fn wigner_of_a_gaussian_is_positive_with_exact_marginal() {
+    let psi: Vec<Complex> = (0..32)
+        .map(|x| Complex::new((-((x as f32 - 16.0) / 3.0).powi(2)).exp(), 0.0))
+        .collect();
+    let w = wigner(&psi);
+    assert!(w.data.iter().all(|&v| v > -1e-4));
+    assert!(marginal_error(&w, &psi) < 1e-4);
+}
@@ -0,0 +1,149 @@
+//! Validation of the solver against closed-form results, in units where ħ = m = 1.
+
+extern crate quantum_wave;
+
+use quantum_wave::{Complex, Coord, Universe};
+use std::f32::consts::PI;
+
+const DT: f32 = 0.1;
+
+fn trap(size: usize, omega: f32) -> Universe {
+    let mut u = Universe::new(size, size);
+    u.setup();
+    let center = (size / 2) as i32;
+    u.add_harmonic_potential(Coord::new(center, center), 0.0, omega, omega);
+    u
+}
+
+#[test]
+fn free_gaussian_width_growth() {
+    // σ(t) = σ₀ √(1 + (t / 2σ₀²)²), within 3%
+    let sigma = 3.0;
+    let mut u = Universe::new(80, 80);
+    u.setup();
+    u.add_wavepacket(40.0, 40.0, sigma, 0.0, 0.0, Complex::new(1.0, 0.0));
+    let steps = 200;
+    for _i in 0..steps {
+        u.step();
+    }
+    let t = steps as f32 * DT;
+    let expected = sigma * (1.0 + (t / (2.0 * sigma * sigma)).powi(2)).sqrt();
+    let obs = u.observables();
+    assert!(
+        (obs.dx - expected).abs() / expected < 0.03,
+        "{} vs {}",
+        obs.dx,
+        expected
+    );
+    assert!(
+        (obs.dy - expected).abs() / expected < 0.03,
+        "{} vs {}",
+        obs.dy,
+        expected
+    );
+}
+
+#[test]
+fn harmonic_oscillator_period() {
+    // a displaced coherent state returns after T = 2π/ω, within 2%
+    let omega = 0.05;
+    let mut u = trap(60, omega);
+    u.add_coherent_state(27.0, 30.0, omega, 0.0, 0.0, Complex::new(1.0, 0.0));
+    let mut previous = u.observables().x;
+    let mut crossings = Vec::new();
+    for i in 1..1700 {
+        u.step();
+        let x = u.observables().x;
+        if (previous - 30.0) * (x - 30.0) < 0.0 {
+            // interpolate the crossing of the trap center
+            let fraction = (previous - 30.0) / (previous - x);
+            crossings.push((i as f32 - 1.0 + fraction) * DT);
+        }
+        previous = x;
+    }
+    assert!(crossings.len() >= 3);
+    let period = crossings[2] - crossings[0];
+    let expected = 2.0 * PI / omega;
+    assert!(
+        (period - expected).abs() / expected < 0.02,
+        "{} vs {}",
+        period,
+        expected
+    );
+}
+
+#[test]
+fn harmonic_oscillator_ground_state_energy() {
+    // E₀ = ω in two dimensions, within 3%, and the state stays put
+    let omega = 0.1;
+    let mut u = trap(60, omega);
+    u.add_coherent_state(30.0, 30.0, omega, 0.0, 0.0, Complex::new(1.0, 0.0));
+    let start = u.observables();
+    assert!(
+        (start.total_energy - omega).abs() / omega < 0.03,
+        "{}",
+        start.total_energy
+    );
+    for _i in 0..300 {
+        u.step();
+    }
+    let end = u.observables();
+    assert!(
+        (end.total_energy - omega).abs() / omega < 0.03,
+        "{}",
+        end.total_energy
+    );
+    assert!((end.dx - start.dx).abs() / start.dx < 0.03);
+}
+
+#[test]
+fn norm_conservation_over_ten_thousand_steps() {
+    // without sinks |ψ|² oscillates by O(dt E) with the staggered leapfrog, within 2%,
+    // but does not drift: its average over the last thousand steps stays within 0.5%
+    let mut u = trap(40, 0.1);
+    u.add_coherent_state(17.0, 22.0, 0.1, 0.2, -0.1, Complex::new(1.0, 0.0));
+    let norm = u.norm();
+    let mut worst: f32 = 0.0;
+    let mut last_average = 0.0;
+    for i in 0..10_000 {
+        u.step();
+        worst = worst.max((u.norm() - norm).abs() / norm);
+        if i >= 9_000 {
+            last_average += u.norm() / 1000.0;
+        }
+    }
+    assert!(worst < 0.02, "{}", worst);
+    assert!(
+        (last_average - norm).abs() / norm < 0.005,
+        "{}",
+        last_average
+    );
+}
+
+#[test]
+fn plane_wave_dispersion() {
+    // packets move at the group velocity of the lattice dispersion 1 - cos k, within 2%,
+    // which approaches the continuum k²/2 at small k, within 5%
+    for &k in [0.2f32, 0.5, 1.0].iter() {
+        let mut u = Universe::new(120, 30);
+        u.setup();
+        u.add_wavepacket(30.0, 15.0, 6.0, k, 0.0, Complex::new(1.0, 0.0));
+        let start = u.observables().x;
+        let steps = 300;
+        for _i in 0..steps {
+            u.step();
+        }
+        let velocity = (u.observables().x - start) / (steps as f32 * DT);
+        // the Gaussian envelope of spread 1/2σ in k lowers the mean of sin k slightly
+        let lattice = k.sin() * (-1.0 / (8.0 * 36.0f32)).exp();
+        assert!(
+            (velocity - lattice).abs() / lattice < 0.02,
+            "{} vs {}",
+            velocity,
+            lattice
+        );
+        if k <= 0.5 {
+            assert!((velocity - k).abs() / k < 0.05, "{} vs {}", velocity, k);
+        }
+    }
+}
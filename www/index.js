@@ -1,9 +1,8 @@
-import { Universe } from "wasm-game-of-life";
+import { Color, Colormap, Image, PotentialStyle, SinkStyle, Universe, VideoFormat } from "wasm-game-of-life";
 import { memory } from "wasm-game-of-life/quantum_wave_bg";
 
 const CELL_SIZE = 10; // px
-const ENTITY_SIZE = 3; // r, g, b values are from 0 to 255.
-const GRID_COLOR = "#333";
+const GRID_COLOR = Color.new(51, 51, 51);
 
 // Construct the universe, and get its width and height.
 const width = 80;
@@ -12,16 +11,33 @@ const universe = Universe.new(width, height);
 
 let active_field = "quantum";
 universe.setup();
+universe.set_cell_size(CELL_SIZE);
+universe.set_cell_grid(true, GRID_COLOR, 1.0);
+universe.set_sink_layer(SinkStyle.Hatch, Color.new(0, 0, 0), 0.5);
 console.log(universe);
 
-// Give the canvas room for all of our cells and a 1px border
-// around each of them.
+// The frame is composited in Rust, walls, sinks, potential and cell grid included.
 const canvas = document.getElementById("game-of-life-canvas");
-canvas.height = (CELL_SIZE + 1) * height + 1;
-canvas.width = (CELL_SIZE + 1) * width + 1;
+universe.render();
+canvas.height = universe.frame_height();
+canvas.width = universe.frame_width();
 
 const ctx = canvas.getContext('2d');
 
+// Views on wasm memory must be rebuilt when the frame is reallocated or memory grows.
+let frameView = null;
+let frameGeneration = -1;
+const frameImage = () => {
+  if (frameView === null
+    || frameGeneration !== universe.frame_generation()
+    || frameView.data.buffer !== memory.buffer) {
+    const pixels = new Uint8ClampedArray(memory.buffer, universe.frame_ptr(), universe.frame_len());
+    frameView = new ImageData(pixels, universe.frame_width(), universe.frame_height());
+    frameGeneration = universe.frame_generation();
+  }
+  return frameView;
+};
+
 const fps = new class {
   constructor() {
     this.fps = document.getElementById("fps");
@@ -80,8 +96,23 @@ const isPaused = () => {
 const resetButton = document.getElementById("reset");
 const playPauseButton = document.getElementById("play-pause");
 const stepButton = document.getElementById("step");
+const recordButton = document.getElementById("record");
 const quantumButton = document.getElementById("quantum");
 const potentialButton = document.getElementById("potential");
+const colormapSelect = document.getElementById("colormap");
+const scalingSelect = document.getElementById("scaling");
+const legendImage = document.getElementById("legend");
+
+// Show a phase wheel for the phase maps and a colorbar of the frame for the others.
+// The exposure the legend was drawn for lets auto exposure redraw it as it drifts.
+let legendExposure = 0;
+const drawLegend = () => {
+  legendExposure = universe.exposure();
+  const phase = colormapSelect.value.startsWith("Phase");
+  const bytes = phase ? universe.phase_wheel_png(48) : universe.colorbar_png(Image.Frame, 160, 36);
+  URL.revokeObjectURL(legendImage.src);
+  legendImage.src = URL.createObjectURL(new Blob([bytes], { type: "image/png" }));
+};
 
 const play = () => {
   playPauseButton.textContent = "⏸";
@@ -108,6 +139,24 @@ playPauseButton.addEventListener("click", event => {
   }
 });
 
+// Record every fifth step to an animated GIF, downloaded when the recording stops.
+let recording = false;
+recordButton.addEventListener("click", event => {
+  if (!recording) {
+    universe.start_recording(VideoFormat.Gif, 5, 20);
+    recordButton.textContent = "⏹️";
+  } else {
+    const bytes = universe.finish_recording();
+    const link = document.createElement("a");
+    link.href = URL.createObjectURL(new Blob([bytes], { type: "image/gif" }));
+    link.download = "quantum-wave.gif";
+    link.click();
+    URL.revokeObjectURL(link.href);
+    recordButton.textContent = "⏺️";
+  }
+  recording = !recording;
+});
+
 stepButton.addEventListener("click", event => {
   universe.step();
   drawCells();
@@ -115,59 +164,46 @@ stepButton.addEventListener("click", event => {
 
 quantumButton.addEventListener("click", event => {
   active_field = "quantum";
+  universe.set_potential_layer(PotentialStyle.Isolines, Color.new(255, 255, 255), 0.5, 0.1);
   drawCells();
 });
 
 potentialButton.addEventListener("click", event => {
   active_field = "potential";
+  universe.set_potential_layer(PotentialStyle.Shading, Color.new(255, 255, 255), 0.8, 0.1);
   drawCells();
 });
 
-const getIndex = (row, column) => {
-  return (row * width + column);
-};
+colormapSelect.addEventListener("change", event => {
+  universe.set_colormap(Colormap[colormapSelect.value]);
+  drawLegend();
+  drawCells();
+});
+
+scalingSelect.addEventListener("change", event => {
+  switch (scalingSelect.value) {
+    case "log":
+      universe.set_log_scaling(2.0, 4.0);
+      break;
+    case "gamma":
+      universe.set_gamma_scaling(2.0, 0.5);
+      break;
+    case "auto":
+      universe.set_auto_exposure(0.99, 0.1);
+      break;
+    default:
+      universe.set_linear_scaling(2.0);
+  }
+  drawLegend();
+  drawCells();
+});
 
 // Render cells.
 const drawCells = () => {
-
-  if (active_field == "quantum") {
-    const cellsPtr = universe.quantum_ptr();
-    const cells = new Uint8Array(memory.buffer, cellsPtr, width * height * ENTITY_SIZE);
-    ctx.beginPath();
-    for (let row = 0; row < height; row++) {
-      for (let col = 0; col < width; col++) {
-        const idx = getIndex(row, col) * ENTITY_SIZE;
-        const r = cells[idx];
-        const g = cells[idx + 1];
-        const b = cells[idx + 2];
-        ctx.fillStyle = `rgb(${r}, ${g}, ${b})`;
-        ctx.fillRect(
-          col * (CELL_SIZE + 1) + 1,
-          row * (CELL_SIZE + 1) + 1,
-          CELL_SIZE,
-          CELL_SIZE
-        );
-      }
-      ctx.stroke();
-    }
-  } else {
-    const cellsPtr = universe.potential_level_ptr();
-    const cells = new Float32Array(memory.buffer, cellsPtr, width * height);
-    ctx.beginPath();
-    for (let row = 0; row < height; row++) {
-      for (let col = 0; col < width; col++) {
-        const idx = getIndex(row, col);
-        const f = 127 + cells[idx] * 127;
-        ctx.fillStyle = `rgb(${f}, ${f}, ${f})`;
-        ctx.fillRect(
-          col * (CELL_SIZE + 1) + 1,
-          row * (CELL_SIZE + 1) + 1,
-          CELL_SIZE,
-          CELL_SIZE
-        );
-      }
-    }
-    ctx.stroke();
+  universe.render();
+  ctx.putImageData(frameImage(), 0, 0);
+  if (Math.abs(universe.exposure() - legendExposure) > 0.01 * legendExposure) {
+    drawLegend();
   }
 };
 
@@ -181,8 +217,8 @@ canvas.addEventListener("click", event => {
   const canvasLeft = (event.clientX - boundingRect.left) * scaleX;
   const canvasTop = (event.clientY - boundingRect.top) * scaleY;
 
-  const y = Math.min(Math.floor(canvasTop / (CELL_SIZE + 1)), height - 1);
-  const x = Math.min(Math.floor(canvasLeft / (CELL_SIZE + 1)), width - 1);
+  const y = Math.min(Math.floor(canvasTop / CELL_SIZE), height - 1);
+  const x = Math.min(Math.floor(canvasLeft / CELL_SIZE), width - 1);
 
   console.log(`Click at ${x}:${y} in field ${active_field}`);
   universe.toggle_cell(x, y, active_field);
@@ -208,5 +244,6 @@ document.addEventListener("keypress", event => {
   }
 });
 
+drawLegend();
 play();
 pause();
\ No newline at end of file